@@ -0,0 +1,465 @@
+use super::*;
+use crate::scale::Scale;
+use egui::epaint::RectShape;
+use egui::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Height of the title bar of comment frames, at zoom 1.0.
+const FRAME_TITLE_HEIGHT: f32 = 24.0;
+/// Size of comment frames created with nothing selected.
+const DEFAULT_FRAME_SIZE: [f32; 2] = [300.0, 200.0];
+const MIN_FRAME_SIZE: [f32; 2] = [80.0, 40.0];
+/// Width of sticky notes, at zoom 1.0.
+const NOTE_WIDTH: f32 = 160.0;
+
+/// The fill color of new comment frames.
+pub const DEFAULT_FRAME_COLOR: Color32 = Color32::from_rgba_premultiplied(40, 60, 100, 60);
+/// The background color of new sticky notes.
+pub const DEFAULT_NOTE_COLOR: Color32 = Color32::from_rgb(250, 230, 140);
+
+/// A titled rectangle drawn behind the nodes, used to annotate a region of the
+/// graph. Dragging a frame by its title moves the nodes and notes inside it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct CommentFrame {
+    pub title: String,
+    /// The area covered by the frame, in graph space.
+    pub rect: egui::Rect,
+    pub color: Color32,
+}
+
+/// A free-floating text note on the canvas.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct StickyNote {
+    pub text: String,
+    /// The top-left corner of the note, in graph space.
+    pub position: egui::Pos2,
+    pub color: Color32,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Adds a comment frame covering `rect`, in graph space.
+    pub fn add_comment_frame(&mut self, rect: egui::Rect, title: impl Into<String>) -> FrameId {
+        self.frames.insert(CommentFrame {
+            title: title.into(),
+            rect,
+            color: DEFAULT_FRAME_COLOR,
+        })
+    }
+
+    /// Adds a sticky note at `position`, in graph space.
+    pub fn add_sticky_note(&mut self, position: egui::Pos2, text: impl Into<String>) -> NoteId {
+        self.notes.insert(StickyNote {
+            text: text.into(),
+            position,
+            color: DEFAULT_NOTE_COLOR,
+        })
+    }
+
+    /// Returns the nodes and notes whose top-left corner lies inside `frame`.
+    /// Nodes that are not drawn, such as the ones inside collapsed groups, are
+    /// left out.
+    pub fn frame_contents(&self, frame: FrameId) -> (Vec<NodeId>, Vec<NoteId>) {
+        let rect = self.frames[frame].rect;
+        let node_groups = self.node_groups();
+        let nodes = self
+            .node_order
+            .iter()
+            .copied()
+            .filter(|node_id| {
+                self.node_positions
+                    .get(*node_id)
+                    .is_some_and(|pos| rect.contains(*pos))
+                    && self.grouped_view(node_groups.get(*node_id).copied()) == NodeView::Visible
+            })
+            .collect();
+        let notes = self
+            .notes
+            .iter()
+            .filter(|(_, note)| rect.contains(note.position))
+            .map(|(note_id, _)| note_id)
+            .collect();
+        (nodes, notes)
+    }
+
+    /// Moves `frame` by `delta`, in graph space, along with its contents.
+    pub fn move_frame(&mut self, frame: FrameId, delta: egui::Vec2) {
+        let (nodes, notes) = self.frame_contents(frame);
+        for node_id in nodes {
+            self.node_positions[node_id] += delta;
+        }
+        for note_id in notes {
+            self.notes[note_id].position += delta;
+        }
+        self.frames[frame].rect = self.frames[frame].rect.translate(delta);
+    }
+
+    /// Removes `frame`. The nodes inside it are left untouched.
+    pub fn remove_frame(&mut self, frame: FrameId) -> Option<CommentFrame> {
+        self.selected_frames.retain(|id| *id != frame);
+        self.frames.remove(frame)
+    }
+
+    /// Removes `note`.
+    pub fn remove_note(&mut self, note: NoteId) -> Option<StickyNote> {
+        self.selected_notes.retain(|id| *id != note);
+        self.notes.remove(note)
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Draws the comment frames and sticky notes, behind the nodes, and applies
+    /// the changes made through them. The returned rects are used for box
+    /// selection.
+    pub(crate) fn draw_annotations(
+        &mut self,
+        ui: &mut Ui,
+        editor_rect: Rect,
+        click_mode: SelectionMode,
+    ) -> AnnotationRects {
+        let zoom = self.pan_zoom.zoom;
+        let mut annotation_actions = Vec::new();
+        let mut frame_rects = Vec::new();
+        for (frame_id, frame) in &self.frames {
+            let rect = Rect::from_min_max(
+                self.pan_zoom
+                    .graph_to_screen(editor_rect.min, frame.rect.min),
+                self.pan_zoom
+                    .graph_to_screen(editor_rect.min, frame.rect.max),
+            );
+            let selected = self.selected_frames.contains(&frame_id);
+            annotation_actions.extend(draw_comment_frame(
+                ui, frame_id, frame, rect, zoom, selected,
+            ));
+            frame_rects.push((frame_id, rect));
+        }
+        let mut note_rects = Vec::new();
+        for (note_id, note) in &self.notes {
+            let screen_pos = self
+                .pan_zoom
+                .graph_to_screen(editor_rect.min, note.position);
+            let selected = self.selected_notes.contains(&note_id);
+            let (action, rect) = draw_sticky_note(ui, note_id, note, screen_pos, zoom, selected);
+            annotation_actions.extend(action);
+            note_rects.push((note_id, rect));
+        }
+
+        for action in annotation_actions {
+            match action {
+                AnnotationAction::MoveFrame(frame, drag_delta) => {
+                    self.record_undo_step(Some(UndoGroup::MoveNodes));
+                    self.move_frame(frame, drag_delta);
+                }
+                AnnotationAction::ResizeFrame(frame, drag_delta) => {
+                    self.record_undo_step(Some(UndoGroup::MoveNodes));
+                    let rect = &mut self.frames[frame].rect;
+                    rect.max = (rect.max + drag_delta).max(rect.min + Vec2::from(MIN_FRAME_SIZE));
+                }
+                AnnotationAction::SetFrameTitle(frame, title) => {
+                    self.record_undo_step(Some(UndoGroup::EditFrameTitle(frame)));
+                    self.frames[frame].title = title;
+                }
+                AnnotationAction::MoveNote(note, drag_delta) => {
+                    self.record_undo_step(Some(UndoGroup::MoveNodes));
+                    self.notes[note].position += drag_delta;
+                }
+                AnnotationAction::SetNoteText(note, text) => {
+                    self.record_undo_step(Some(UndoGroup::EditNoteText(note)));
+                    self.notes[note].text = text;
+                }
+                AnnotationAction::DeleteFrame(frame) => {
+                    self.record_undo_step(None);
+                    self.remove_frame(frame);
+                }
+                AnnotationAction::DeleteNote(note) => {
+                    self.record_undo_step(None);
+                    self.remove_note(note);
+                }
+                AnnotationAction::SelectFrame(frame) => {
+                    if click_mode == SelectionMode::Replace {
+                        self.selected_nodes.clear();
+                        self.selected_notes.clear();
+                    }
+                    self.selected_frames = click_mode.combine(&self.selected_frames, [frame]);
+                }
+                AnnotationAction::SelectNote(note) => {
+                    if click_mode == SelectionMode::Replace {
+                        self.selected_nodes.clear();
+                        self.selected_frames.clear();
+                    }
+                    self.selected_notes = click_mode.combine(&self.selected_notes, [note]);
+                }
+            }
+        }
+
+        AnnotationRects {
+            frames: frame_rects,
+            notes: note_rects,
+        }
+    }
+
+    /// Adds a comment frame around the selected nodes or, when none of them
+    /// are drawn, at `cursor_pos`. Frames are sized from the node rects, known
+    /// after drawing the nodes.
+    pub(crate) fn add_frame_around_selection(
+        &mut self,
+        editor_rect: Rect,
+        node_rects: &NodeRects,
+        cursor_pos: Pos2,
+    ) {
+        let selection = self
+            .selected_nodes
+            .iter()
+            .filter_map(|node_id| node_rects.get(node_id).copied())
+            .fold(Rect::NOTHING, Rect::union);
+        let rect = if selection.is_positive() {
+            let mut rect = Rect::from_min_max(
+                self.pan_zoom
+                    .screen_to_graph(editor_rect.min, selection.min),
+                self.pan_zoom
+                    .screen_to_graph(editor_rect.min, selection.max),
+            )
+            .expand(GROUP_FRAME_MARGIN);
+            rect.min.y -= FRAME_TITLE_HEIGHT;
+            rect
+        } else {
+            Rect::from_min_size(
+                self.pan_zoom.screen_to_graph(editor_rect.min, cursor_pos),
+                Vec2::from(DEFAULT_FRAME_SIZE),
+            )
+        };
+        self.record_undo_step(None);
+        let frame = self.add_comment_frame(rect, "Comment");
+        self.selected_frames = vec![frame];
+    }
+}
+
+/// The screen rects of the drawn comment frames and sticky notes.
+pub(crate) struct AnnotationRects {
+    pub frames: Vec<(FrameId, Rect)>,
+    pub notes: Vec<(NoteId, Rect)>,
+}
+
+/// Changes to comment frames and sticky notes requested from their widgets.
+/// Drag deltas are in graph space.
+enum AnnotationAction {
+    MoveFrame(FrameId, Vec2),
+    ResizeFrame(FrameId, Vec2),
+    SetFrameTitle(FrameId, String),
+    DeleteFrame(FrameId),
+    SelectFrame(FrameId),
+    MoveNote(NoteId, Vec2),
+    SetNoteText(NoteId, String),
+    DeleteNote(NoteId),
+    SelectNote(NoteId),
+}
+
+/// Draws a comment frame covering `rect`, in screen space. Only the title
+/// bar and the resize handle are interactive, so the background of the frame
+/// can still be clicked and box selected.
+fn draw_comment_frame(
+    ui: &mut Ui,
+    frame_id: FrameId,
+    frame: &CommentFrame,
+    rect: Rect,
+    zoom: f32,
+    selected: bool,
+) -> Option<AnnotationAction> {
+    let rounding = 4.0 * zoom;
+    let title_rect = Rect::from_min_size(rect.min, vec2(rect.width(), FRAME_TITLE_HEIGHT * zoom));
+    ui.painter().rect_filled(rect, rounding, frame.color);
+    ui.painter().rect_filled(
+        title_rect,
+        Rounding {
+            nw: rounding,
+            ne: rounding,
+            sw: 0.0,
+            se: 0.0,
+        },
+        frame.color,
+    );
+    if selected {
+        ui.painter()
+            .rect_stroke(rect, rounding, Stroke::new(2.0 * zoom, Color32::WHITE));
+    }
+
+    let mut action = None;
+    let id = Id::new((frame_id, "comment_frame"));
+
+    let title_response = ui.interact(title_rect, id.with("title"), Sense::click_and_drag());
+    let drag_delta = title_response.drag_delta() / zoom;
+    if drag_delta.length_sq() > 0.0 {
+        action = Some(AnnotationAction::MoveFrame(frame_id, drag_delta));
+    } else if title_response.clicked() {
+        action = Some(AnnotationAction::SelectFrame(frame_id));
+    }
+
+    let handle_size = 12.0 * zoom;
+    let handle_rect = Rect::from_min_max(rect.max - Vec2::splat(handle_size), rect.max);
+    let handle_response = ui
+        .interact(handle_rect, id.with("resize"), Sense::drag())
+        .on_hover_cursor(CursorIcon::ResizeNwSe);
+    let handle_stroke = Stroke::new(1.0 * zoom, ui.visuals().weak_text_color());
+    for offset in [0.3, 0.65] {
+        let offset = handle_size * offset;
+        ui.painter().line_segment(
+            [
+                pos2(rect.max.x - offset, rect.max.y - 2.0 * zoom),
+                pos2(rect.max.x - 2.0 * zoom, rect.max.y - offset),
+            ],
+            handle_stroke,
+        );
+    }
+    let resize_delta = handle_response.drag_delta() / zoom;
+    if resize_delta.length_sq() > 0.0 {
+        action = Some(AnnotationAction::ResizeFrame(frame_id, resize_delta));
+    }
+
+    let mut title_ui = ui.child_ui_with_id_source(
+        title_rect.shrink2(vec2(6.0 * zoom, 0.0)),
+        Layout::left_to_right(Align::Center),
+        id,
+    );
+    title_ui.style_mut().scale(zoom);
+    let mut title = frame.title.clone();
+    let title_width = (title_rect.width() - 40.0 * zoom).max(0.0);
+    if title_ui
+        .add(
+            TextEdit::singleline(&mut title)
+                .frame(false)
+                .desired_width(title_width),
+        )
+        .changed()
+    {
+        action = Some(AnnotationAction::SetFrameTitle(frame_id, title));
+    }
+    if title_ui.small_button("🗙").clicked() {
+        action = Some(AnnotationAction::DeleteFrame(frame_id));
+    }
+    action
+}
+
+/// Draws a sticky note with its top-left corner at `screen_pos`. Returns the
+/// rect covered by the note, used for box selection.
+fn draw_sticky_note(
+    ui: &mut Ui,
+    note_id: NoteId,
+    note: &StickyNote,
+    screen_pos: Pos2,
+    zoom: f32,
+    selected: bool,
+) -> (Option<AnnotationAction>, Rect) {
+    let margin = Vec2::splat(6.0 * zoom);
+    let mut note_ui = ui.child_ui_with_id_source(
+        Rect::from_min_size(screen_pos, vec2(NOTE_WIDTH, NOTE_WIDTH) * zoom),
+        Layout::top_down(Align::Min),
+        (note_id, "sticky_note"),
+    );
+    note_ui.style_mut().scale(zoom);
+    let background_shape = note_ui.painter().add(Shape::Noop);
+
+    // The whole note can be dragged, except for the text and the buttons
+    // drawn on top of it. The rect is taken from the previous frame.
+    let id = note_ui.id().with("background");
+    let interaction_rect = note_ui
+        .ctx()
+        .memory_mut(|mem| mem.data.get_temp::<OuterRectMemory>(id))
+        .map_or(Rect::NOTHING, |stored| stored.0);
+    let response = note_ui.interact(interaction_rect, id, Sense::click_and_drag());
+
+    let mut action = None;
+    let drag_delta = response.drag_delta() / zoom;
+    if drag_delta.length_sq() > 0.0 {
+        action = Some(AnnotationAction::MoveNote(note_id, drag_delta));
+    } else if response.clicked() {
+        action = Some(AnnotationAction::SelectNote(note_id));
+    }
+
+    let text_color = Color32::from_gray(40);
+    let mut content_ui = note_ui.child_ui(note_ui.max_rect().shrink2(margin), Layout::default());
+    content_ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+        ui.visuals_mut().override_text_color = Some(text_color);
+        if ui.small_button("🗙").clicked() {
+            action = Some(AnnotationAction::DeleteNote(note_id));
+        }
+    });
+    let mut text = note.text.clone();
+    if content_ui
+        .add(
+            TextEdit::multiline(&mut text)
+                .frame(false)
+                .desired_rows(1)
+                .text_color(text_color)
+                .desired_width(content_ui.available_width()),
+        )
+        .changed()
+    {
+        action = Some(AnnotationAction::SetNoteText(note_id, text));
+    }
+
+    let rect = content_ui.min_rect().expand2(margin);
+    note_ui
+        .ctx()
+        .memory_mut(|mem| mem.data.insert_temp(id, OuterRectMemory(rect)));
+    let stroke = if selected {
+        Stroke::new(2.0 * zoom, Color32::WHITE)
+    } else {
+        Stroke::NONE
+    };
+    note_ui.painter().set(
+        background_shape,
+        Shape::Rect(RectShape::new(rect, 4.0 * zoom, note.color, stroke)),
+    );
+    (action, rect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_move_frame_contents() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let inside = state.graph.add_node(String::new(), (), |_, _| {});
+        let outside = state.graph.add_node(String::new(), (), |_, _| {});
+        let grouped = state.graph.add_node(String::new(), (), |_, _| {});
+        state.node_order = vec![inside, outside, grouped];
+        state.node_positions.insert(inside, egui::pos2(10.0, 10.0));
+        state
+            .node_positions
+            .insert(outside, egui::pos2(500.0, 10.0));
+        state.node_positions.insert(grouped, egui::pos2(20.0, 20.0));
+        // Nodes hidden inside a collapsed group are not moved with the frame.
+        state.create_group(&[grouped], "group");
+        let note = state.add_sticky_note(egui::pos2(50.0, 50.0), "note");
+
+        let frame = state.add_comment_frame(
+            egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(100.0, 100.0)),
+            "frame",
+        );
+        assert_eq!(state.frame_contents(frame), (vec![inside], vec![note]));
+
+        state.move_frame(frame, egui::vec2(5.0, 0.0));
+        assert_eq!(state.node_positions[inside], egui::pos2(15.0, 10.0));
+        assert_eq!(state.node_positions[outside], egui::pos2(500.0, 10.0));
+        assert_eq!(state.node_positions[grouped], egui::pos2(20.0, 20.0));
+        assert_eq!(state.notes[note].position, egui::pos2(55.0, 50.0));
+        assert_eq!(state.frames[frame].rect.min, egui::pos2(5.0, 0.0));
+
+        state.selected_frames.push(frame);
+        assert!(state.remove_frame(frame).is_some());
+        assert!(state.selected_frames.is_empty());
+        assert!(state.notes.contains_key(note));
+    }
+}
@@ -0,0 +1,355 @@
+use super::*;
+use std::num::NonZeroU32;
+
+#[cfg(feature = "persistence")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Offset, in graph units, between duplicated nodes and their originals.
+pub const DUPLICATE_OFFSET: egui::Vec2 = egui::vec2(20.0, 20.0);
+
+/// Bounds required on the user types to copy nodes to the system clipboard.
+/// With the `persistence` feature, clipboard contents are serialized as text,
+/// so they can be pasted in another editor. Otherwise, this trait has no
+/// requirements and copied nodes can only be pasted in the same editor.
+#[cfg(feature = "persistence")]
+pub trait ClipboardDataTrait: Serialize + DeserializeOwned {}
+#[cfg(feature = "persistence")]
+impl<T: Serialize + DeserializeOwned> ClipboardDataTrait for T {}
+
+/// Bounds required on the user types to copy nodes to the system clipboard.
+/// With the `persistence` feature, clipboard contents are serialized as text,
+/// so they can be pasted in another editor. Otherwise, this trait has no
+/// requirements and copied nodes can only be pasted in the same editor.
+#[cfg(not(feature = "persistence"))]
+pub trait ClipboardDataTrait {}
+#[cfg(not(feature = "persistence"))]
+impl<T> ClipboardDataTrait for T {}
+
+/// A set of copied nodes, along with their input values and the connections
+/// between them. Ids are replaced by indices, so the nodes can be pasted any
+/// number of times, in any graph.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct GraphClipboard<NodeData, DataType, ValueType> {
+    pub nodes: Vec<ClipboardNode<NodeData, DataType, ValueType>>,
+    pub connections: Vec<ClipboardConnection>,
+}
+
+/// A copied node. See [`GraphClipboard`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct ClipboardNode<NodeData, DataType, ValueType> {
+    pub label: String,
+    pub user_data: NodeData,
+    /// The position of the node in the graph it was copied from.
+    pub position: egui::Pos2,
+    pub inputs: Vec<ClipboardInput<DataType, ValueType>>,
+    pub outputs: Vec<(String, DataType)>,
+}
+
+/// A copied input parameter. See [`GraphClipboard`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct ClipboardInput<DataType, ValueType> {
+    pub name: String,
+    pub typ: DataType,
+    pub value: ValueType,
+    pub kind: InputParamKind,
+    pub max_connections: Option<NonZeroU32>,
+    pub shown_inline: bool,
+}
+
+/// A copied connection. Nodes are referred to by their index in
+/// [`GraphClipboard::nodes`], and ports by their index inside the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct ClipboardConnection {
+    pub output_node: usize,
+    pub output: usize,
+    pub input_node: usize,
+    pub input: usize,
+}
+
+impl<NodeData, DataType, ValueType> GraphClipboard<NodeData, DataType, ValueType>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Copies `nodes` from `graph`, along with the connections between them.
+    /// Connections to nodes outside of `nodes` are left out.
+    pub fn copy(
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_positions: &SecondaryMap<NodeId, egui::Pos2>,
+        nodes: &[NodeId],
+    ) -> Self {
+        let index_of = |node_id: NodeId| nodes.iter().position(|n| *n == node_id);
+
+        let mut connections = Vec::new();
+        for (input_node, node_id) in nodes.iter().copied().enumerate() {
+            for (input, (_, input_id)) in graph[node_id].inputs.iter().enumerate() {
+                for output_id in graph.connections(*input_id) {
+                    let output_node_id = graph[*output_id].node;
+                    let Some(output_node) = index_of(output_node_id) else {
+                        continue;
+                    };
+                    let output = graph[output_node_id]
+                        .outputs
+                        .iter()
+                        .position(|(_, id)| id == output_id)
+                        .expect("Outputs should belong to their node");
+                    connections.push(ClipboardConnection {
+                        output_node,
+                        output,
+                        input_node,
+                        input,
+                    });
+                }
+            }
+        }
+
+        let nodes = nodes
+            .iter()
+            .map(|node_id| {
+                let node = &graph[*node_id];
+                ClipboardNode {
+                    label: node.label.clone(),
+                    user_data: node.user_data.clone(),
+                    position: node_positions
+                        .get(*node_id)
+                        .copied()
+                        .unwrap_or(egui::Pos2::ZERO),
+                    inputs: node
+                        .inputs
+                        .iter()
+                        .map(|(name, input_id)| {
+                            let input = &graph[*input_id];
+                            ClipboardInput {
+                                name: name.clone(),
+                                typ: input.typ.clone(),
+                                value: input.value.clone(),
+                                kind: input.kind,
+                                max_connections: input.max_connections,
+                                shown_inline: input.shown_inline,
+                            }
+                        })
+                        .collect(),
+                    outputs: node
+                        .outputs
+                        .iter()
+                        .map(|(name, output_id)| (name.clone(), graph[*output_id].typ.clone()))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Self { nodes, connections }
+    }
+
+    /// The top-left corner of the bounding box of the copied node positions.
+    pub fn origin(&self) -> egui::Pos2 {
+        self.nodes
+            .iter()
+            .map(|node| node.position)
+            .reduce(|a, b| a.min(b))
+            .unwrap_or(egui::Pos2::ZERO)
+    }
+
+    /// Adds the copied nodes to `graph`, with fresh ids, so that the
+    /// [`origin`](Self::origin) of the copied nodes lands at `position`.
+    /// Returns the ids of the new nodes, in the same order as
+    /// [`GraphClipboard::nodes`].
+    pub fn paste(
+        &self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        node_positions: &mut SecondaryMap<NodeId, egui::Pos2>,
+        position: egui::Pos2,
+    ) -> Vec<NodeId> {
+        let offset = position - self.origin();
+        let new_nodes: Vec<NodeId> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let node_id = graph.add_node(
+                    node.label.clone(),
+                    node.user_data.clone(),
+                    |graph, node_id| {
+                        for input in &node.inputs {
+                            graph.add_wide_input_param(
+                                node_id,
+                                input.name.clone(),
+                                input.typ.clone(),
+                                input.value.clone(),
+                                input.kind,
+                                input.shown_inline,
+                                input.max_connections,
+                            );
+                        }
+                        for (name, typ) in &node.outputs {
+                            graph.add_output_param(node_id, name.clone(), typ.clone());
+                        }
+                    },
+                );
+                node_positions.insert(node_id, node.position + offset);
+                node_id
+            })
+            .collect();
+
+        for connection in &self.connections {
+            let output = new_nodes
+                .get(connection.output_node)
+                .and_then(|node_id| graph[*node_id].outputs.get(connection.output))
+                .map(|(_, output)| *output);
+            let input = new_nodes
+                .get(connection.input_node)
+                .and_then(|node_id| graph[*node_id].inputs.get(connection.input))
+                .map(|(_, input)| *input);
+            // Clipboard text may come from anywhere, so invalid indices are
+            // skipped instead of panicking.
+            if let (Some(output), Some(input)) = (output, input) {
+                graph.add_connection(output, input);
+            }
+        }
+
+        new_nodes
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl<NodeData, DataType, ValueType> GraphClipboard<NodeData, DataType, ValueType>
+where
+    NodeData: ClipboardDataTrait,
+    DataType: ClipboardDataTrait,
+    ValueType: ClipboardDataTrait,
+{
+    /// Serializes the copied nodes, to be stored in the system clipboard.
+    pub fn to_text(&self) -> Option<String> {
+        ron::to_string(self).ok()
+    }
+
+    /// Parses text produced by [`to_text`](Self::to_text). Returns `None` if
+    /// the text doesn't contain copied nodes.
+    pub fn from_text(text: &str) -> Option<Self> {
+        ron::from_str(text).ok()
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Copies the selected nodes. Returns `None` when no node is selected.
+    pub fn copy_selection(&self) -> Option<GraphClipboard<NodeData, DataType, ValueType>> {
+        if self.selected_nodes.is_empty() {
+            None
+        } else {
+            Some(GraphClipboard::copy(
+                &self.graph,
+                &self.node_positions,
+                &self.selected_nodes,
+            ))
+        }
+    }
+
+    /// Adds the nodes in `clipboard` to the editor at `position`, in graph
+    /// space, and selects them. Returns the ids of the new nodes.
+    pub fn paste(
+        &mut self,
+        clipboard: &GraphClipboard<NodeData, DataType, ValueType>,
+        position: egui::Pos2,
+    ) -> Vec<NodeId> {
+        let new_nodes = clipboard.paste(&mut self.graph, &mut self.node_positions, position);
+        self.node_order.extend(new_nodes.iter().copied());
+        self.add_to_open_group(&new_nodes);
+        self.selected_nodes = new_nodes.clone();
+        new_nodes
+    }
+
+    /// Creates a copy of the selected nodes, offset by [`DUPLICATE_OFFSET`],
+    /// and selects it. Returns the ids of the new nodes.
+    pub fn duplicate_selection(&mut self) -> Vec<NodeId> {
+        match self.copy_selection() {
+            Some(clipboard) => self.paste(&clipboard, clipboard.origin() + DUPLICATE_OFFSET),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = Graph<(), (), u32>;
+
+    fn add_node(graph: &mut TestGraph, value: u32) -> NodeId {
+        graph.add_node(String::new(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                (),
+                value,
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            graph.add_output_param(node_id, "out".into(), ());
+        })
+    }
+
+    fn connect(graph: &mut TestGraph, from: NodeId, to: NodeId) {
+        let output = graph[from].get_output("out").unwrap();
+        let input = graph[to].get_input("in").unwrap();
+        graph.add_connection(output, input);
+    }
+
+    #[test]
+    pub fn test_copy_paste() {
+        let mut graph = TestGraph::new();
+        let mut positions = SecondaryMap::new();
+        let [a, b, c] = [1, 2, 3].map(|value| add_node(&mut graph, value));
+        positions.insert(a, egui::pos2(10.0, 50.0));
+        positions.insert(b, egui::pos2(100.0, 20.0));
+        connect(&mut graph, a, b);
+        connect(&mut graph, b, c);
+
+        let clipboard = GraphClipboard::copy(&graph, &positions, &[a, b]);
+        assert_eq!(clipboard.connections.len(), 1);
+        assert_eq!(clipboard.origin(), egui::pos2(10.0, 20.0));
+
+        let pasted = clipboard.paste(&mut graph, &mut positions, egui::pos2(0.0, 0.0));
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(positions[pasted[0]], egui::pos2(0.0, 30.0));
+        assert_eq!(positions[pasted[1]], egui::pos2(90.0, 0.0));
+
+        let [new_a, new_b] = [pasted[0], pasted[1]];
+        assert!(![a, b, c].contains(&new_a) && ![a, b, c].contains(&new_b));
+        let new_input = graph[new_b].get_input("in").unwrap();
+        assert_eq!(graph[new_input].value, 2);
+        assert_eq!(
+            graph.connections(new_input),
+            &[graph[new_a].get_output("out").unwrap()]
+        );
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    pub fn test_clipboard_text() {
+        let mut graph = TestGraph::new();
+        let mut positions = SecondaryMap::new();
+        let [a, b] = [1, 2].map(|value| add_node(&mut graph, value));
+        connect(&mut graph, a, b);
+
+        let text = GraphClipboard::copy(&graph, &positions, &[a, b])
+            .to_text()
+            .unwrap();
+        let clipboard = GraphClipboard::<(), (), u32>::from_text(&text).unwrap();
+        assert_eq!(clipboard.nodes.len(), 2);
+        assert_eq!(clipboard.connections.len(), 1);
+        clipboard.paste(&mut graph, &mut positions, egui::Pos2::ZERO);
+        assert_eq!(graph.iter_connections().count(), 2);
+
+        assert!(GraphClipboard::<(), (), u32>::from_text("not nodes").is_none());
+    }
+}
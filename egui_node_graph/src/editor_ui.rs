@@ -1,16 +1,23 @@
 use std::collections::HashSet;
+use std::num::NonZeroU32;
 
-use crate::color_hex_utils::*;
-use crate::utils::ColorUtils;
+use crate::scale::Scale;
 
 use super::*;
-use egui::epaint::{CubicBezierShape, RectShape};
+use egui::epaint::RectShape;
 use egui::*;
 
 pub type PortLocations = std::collections::HashMap<AnyParameterId, Pos2>;
 pub type NodeRects = std::collections::HashMap<NodeId, Rect>;
 
 const DISTANCE_TO_CONNECT: f32 = 10.0;
+/// Vertical distance between the wires entering an input with several
+/// connections, at zoom 1.0.
+const WIRE_FAN_SPACING: f32 = 6.0;
+/// Distance the arrow keys move the selection by, in graph space. Holding
+/// shift moves it by `NUDGE_DISTANCE_LARGE` instead.
+const NUDGE_DISTANCE: f32 = 1.0;
+const NUDGE_DISTANCE_LARGE: f32 = 10.0;
 
 /// Nodes communicate certain events to the parent graph when drawn. There is
 /// one special `User` variant which can be used by users as the return value
@@ -44,6 +51,18 @@ pub enum NodeResponse<UserResponse: UserResponseTrait, NodeData: NodeDataTrait>
         node: NodeId,
         drag_delta: Vec2,
     },
+    /// Emitted when the resize handle of a node is dragged. The size is the
+    /// one requested by the drag, in graph space, before applying the
+    /// [`NodeSizeConstraints`] of the node.
+    ResizeNode {
+        node: NodeId,
+        size: Vec2,
+    },
+    /// Emitted when the collapse toggle in the title bar of a node is clicked.
+    CollapseNode {
+        node: NodeId,
+        collapsed: bool,
+    },
     User(UserResponse),
 }
 
@@ -80,21 +99,38 @@ pub struct GraphNodeWidget<'a, NodeData, DataType, ValueType> {
     pub node_id: NodeId,
     pub ongoing_drag: Option<(NodeId, AnyParameterId)>,
     pub selected: bool,
+    /// The size chosen by the user for the node, if it was resized. See
+    /// [`GraphEditorState::custom_node_sizes`].
+    pub size: Option<Vec2>,
+    /// Draws only the title bar of the node. See
+    /// [`GraphEditorState::collapsed_nodes`].
+    pub collapsed: bool,
     pub pan: egui::Vec2,
+    pub zoom: f32,
+    /// Set while dragging a wire when [`GraphEditorState::reject_cycles`] is
+    /// enabled.
+    pub cycle_guard: Option<&'a CycleGuard>,
+    pub style: &'a GraphStyle,
+    /// Filled with the previous value of every inline value edited while
+    /// drawing the node, so the editor can record undo steps.
+    pub value_edits: &'a mut Vec<(InputId, ValueType)>,
 }
 
 impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
     GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
 where
     NodeData: NodeDataTrait<
-        Response = UserResponse,
-        UserState = UserState,
-        DataType = DataType,
-        ValueType = ValueType,
-    >,
+            Response = UserResponse,
+            UserState = UserState,
+            DataType = DataType,
+            ValueType = ValueType,
+        > + Clone
+        + ClipboardDataTrait,
     UserResponse: UserResponseTrait,
-    ValueType:
-        WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>,
+    ValueType: WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>
+        + Clone
+        + PartialEq
+        + ClipboardDataTrait,
     NodeTemplate: NodeTemplateTrait<
         NodeData = NodeData,
         DataType = DataType,
@@ -102,7 +138,7 @@ where
         UserState = UserState,
         CategoryType = CategoryType,
     >,
-    DataType: DataTypeTrait<UserState>,
+    DataType: DataTypeTrait<UserState> + Clone + ClipboardDataTrait,
     CategoryType: CategoryTrait,
 {
     #[must_use]
@@ -123,7 +159,19 @@ where
             .ctx()
             .input(|i| i.pointer.hover_pos().unwrap_or(Pos2::ZERO));
         let mut cursor_in_editor = resp.hovered();
-        let mut cursor_in_finder = false;
+
+        // Clicking while holding shift adds to the selection, and holding ctrl
+        // toggles the clicked item.
+        let modifiers = ui.ctx().input(|i| i.modifiers);
+        let click_mode = if modifiers.shift {
+            SelectionMode::Add
+        } else if modifiers.command {
+            SelectionMode::Toggle
+        } else {
+            SelectionMode::Replace
+        };
+        // Holding alt places nodes freely, even when snapping is enabled.
+        let snap_to_grid = self.grid.snap && !modifiers.alt;
 
         // Gets filled with the node metrics as they are drawn
         let mut port_locations = PortLocations::new();
@@ -140,12 +188,25 @@ where
         let mut drag_started_on_background = false;
         let mut drag_released_on_background = false;
 
-        debug_assert_eq!(
-            self.node_order.iter().copied().collect::<HashSet<_>>(),
-            self.graph.iter_nodes().collect::<HashSet<_>>(),
-            "The node_order field of the GraphEditorself was left in an \
-        inconsistent self. It has either more or less values than the graph."
-        );
+        // States persisted before zooming was supported may store a zero zoom.
+        if self.pan_zoom.zoom <= 0.0 {
+            self.pan_zoom.zoom = 1.0;
+        }
+
+        // Zoom around the cursor with ctrl+scroll or pinch gestures. This
+        // happens before drawing so the whole frame uses the same zoom.
+        if resp.hovered() {
+            let zoom_delta = ui.ctx().input(|i| i.zoom_delta());
+            if zoom_delta != 1.0 {
+                self.view_animation = None;
+                self.pan_zoom.adjust_zoom(
+                    zoom_delta,
+                    cursor_pos - editor_rect.min,
+                    self.min_zoom,
+                    self.max_zoom,
+                );
+            }
+        }
 
         // Allocate rect before the nodes, otherwise this will block the interaction
         // with the nodes.
@@ -158,8 +219,90 @@ where
             drag_released_on_background = true;
         }
 
+        // The editor takes the keyboard focus when pressed, so its shortcuts
+        // don't run while the user works elsewhere. Text fields inside the
+        // nodes are drawn later and take it back when they are pressed.
+        if r.hovered() && ui.input(|i| i.pointer.any_pressed()) {
+            r.request_focus();
+        }
+        let deferred = self.handle_shortcuts(
+            ui,
+            r.has_focus(),
+            editor_rect,
+            cursor_pos,
+            snap_to_grid,
+            &mut delayed_responses,
+            user_state,
+        );
+        if let Some(request) = self.view_request.take() {
+            if let Some(target) = self.resolve_view_request(request, editor_rect.size()) {
+                self.move_view(target);
+            }
+        }
+        let dt = ui.ctx().input(|i| i.stable_dt).min(0.1);
+        if self.advance_view_animation(dt, editor_rect.size()) {
+            ui.ctx().request_repaint();
+        }
+        let zoom = self.pan_zoom.zoom;
+        let style = self
+            .style
+            .clone()
+            .unwrap_or_else(|| GraphStyle::from_visuals(ui.visuals()));
+
+        debug_assert_eq!(
+            self.node_order.iter().copied().collect::<HashSet<_>>(),
+            self.graph.iter_nodes().collect::<HashSet<_>>(),
+            "The node_order field of the GraphEditorself was left in an \
+        inconsistent self. It has either more or less values than the graph."
+        );
+
+        if self.grid.show {
+            self.grid.draw(ui, editor_rect, &self.pan_zoom);
+        }
+
+        // Gets filled with the previous values of inline widgets edited while
+        // drawing the nodes.
+        let mut value_edits = Vec::new();
+
+        // Ports that would close a cycle are found once per frame, not for
+        // every port the dragged wire passes over.
+        let cycle_guard = self
+            .connection_in_progress
+            .filter(|_| self.reject_cycles)
+            .filter(|(_, origin)| self.graph.any_param_type(*origin).is_ok())
+            .map(|(node, origin)| CycleGuard::new(&self.graph, node, origin));
+
+        // Nodes inside collapsed groups, or outside the entered group, are not
+        // drawn. Only the visible nodes can stay selected.
+        if self
+            .open_group
+            .is_some_and(|group| !self.groups.contains_key(group))
+        {
+            self.open_group = None;
+        }
+        let node_groups = self.node_groups();
+        let node_views: SecondaryMap<NodeId, NodeView> = self
+            .node_order
+            .iter()
+            .map(|node_id| {
+                let group = node_groups.get(*node_id).copied();
+                (*node_id, self.grouped_view(group))
+            })
+            .collect();
+        let is_visible = |node_id: NodeId| node_views.get(node_id) == Some(&NodeView::Visible);
+        self.selected_nodes.retain(|node_id| is_visible(*node_id));
+
+        // Frames around expanded groups are painted behind the nodes.
+        let group_frames_shape = ui.painter().add(Shape::Noop);
+
+        /* Draw comment frames and sticky notes, behind the nodes */
+        let annotation_rects = self.draw_annotations(ui, editor_rect, click_mode);
+
         /* Draw nodes */
         for node_id in self.node_order.iter().copied() {
+            if !is_visible(node_id) {
+                continue;
+            }
             let responses = GraphNodeWidget {
                 position: self.node_positions.get_mut(node_id).unwrap(),
                 graph: &mut self.graph,
@@ -167,11 +310,14 @@ where
                 node_rects: &mut node_rects,
                 node_id,
                 ongoing_drag: self.connection_in_progress,
-                selected: self
-                    .selected_nodes
-                    .iter()
-                    .any(|selected| *selected == node_id),
+                selected: self.selected_nodes.contains(&node_id),
+                size: self.custom_node_sizes.get(node_id).copied(),
+                collapsed: self.collapsed_nodes.contains_key(node_id),
                 pan: self.pan_zoom.pan + editor_rect.min.to_vec2(),
+                zoom,
+                cycle_guard: cycle_guard.as_ref(),
+                style: &style,
+                value_edits: &mut value_edits,
             }
             .show(ui, user_state);
 
@@ -179,74 +325,591 @@ where
             delayed_responses.extend(responses);
         }
 
+        if deferred.add_frame {
+            self.add_frame_around_selection(editor_rect, &node_rects, cursor_pos);
+        }
+
+        // Remember the node sizes, for the automatic layout
+        for (node_id, rect) in node_rects.iter() {
+            self.node_sizes.insert(*node_id, rect.size() / zoom);
+        }
+        let cursor_over_node = node_rects.values().any(|rect| rect.contains(cursor_pos));
+
+        /* Draw groups */
+        self.draw_groups(
+            ui,
+            editor_rect,
+            &style,
+            &node_views,
+            &node_groups,
+            &node_rects,
+            &mut port_locations,
+            group_frames_shape,
+            user_state,
+        );
+
+        // Inline values are edited in place while drawing. To record the state
+        // before the edit, the old values are swapped back in temporarily.
+        if !value_edits.is_empty() {
+            let group = match value_edits.as_slice() {
+                [(input, _)] => Some(UndoGroup::EditValue(*input)),
+                _ => None,
+            };
+            for (input, value) in value_edits.iter_mut() {
+                std::mem::swap(&mut self.graph[*input].value, value);
+            }
+            self.record_undo_step(group);
+            for (input, value) in value_edits.iter_mut() {
+                std::mem::swap(&mut self.graph[*input].value, value);
+            }
+        }
+
+        // Some responses generate additional responses when processed. These
+        // are stored here to report them back to the user.
+        let mut extra_responses: Vec<NodeResponse<UserResponse, NodeData>> = Vec::new();
+
         /* Draw the node finder, if open */
-        let mut should_close_node_finder = false;
+        let cursor_in_finder = self.draw_node_finder(
+            ui,
+            &r,
+            &all_kinds,
+            editor_rect,
+            cursor_pos,
+            snap_to_grid,
+            &style,
+            &mut delayed_responses,
+            &mut extra_responses,
+            user_state,
+        );
+        // The finder counts as part of the editor.
+        if cursor_in_finder {
+            cursor_in_editor = true;
+        }
+
+        /* Draw the dragged wire */
+        let dragging_wire = self.draw_dragged_wire(
+            ui,
+            cursor_pos,
+            &style,
+            &port_locations,
+            cycle_guard.as_ref(),
+            user_state,
+        );
+
+        /* Draw wires, with their reroute points */
+        self.draw_wires(
+            ui,
+            &r,
+            editor_rect,
+            cursor_pos,
+            &style,
+            &node_views,
+            &port_locations,
+            dragging_wire,
+            user_state,
+        );
+
+        /* Draw the minimap */
+        let cursor_in_minimap =
+            self.draw_minimap(ui, editor_rect, &node_rects, &port_locations, user_state);
+
+        /* Handle responses from drawing nodes */
+
+        self.handle_node_responses(
+            ui,
+            &delayed_responses,
+            click_mode,
+            snap_to_grid,
+            &mut extra_responses,
+            user_state,
+        );
+
+        // Selected frames and notes are deleted in the same undo step as the
+        // selected nodes, which was recorded above when there were any.
+        if deferred.delete_selection
+            && (!self.selected_frames.is_empty() || !self.selected_notes.is_empty())
+        {
+            let deleted_nodes = delayed_responses
+                .iter()
+                .any(|response| matches!(response, NodeResponse::DeleteNodeUi(_)));
+            if !deleted_nodes {
+                self.record_undo_step(None);
+            }
+            for frame in std::mem::take(&mut self.selected_frames) {
+                self.remove_frame(frame);
+            }
+            for note in std::mem::take(&mut self.selected_notes) {
+                self.remove_note(note);
+            }
+        }
+
+        // Handle box selection
+        self.update_box_selection(ui, cursor_pos, &style, node_rects, annotation_rects);
+
+        // Push any responses that were generated during response handling.
+        // These are only informative for the end-user and need no special
+        // treatment here.
+        delayed_responses.extend(extra_responses);
+
+        /* Context menu */
+        let (show_canvas_menu, menu_used) = self.canvas_context_menu(&r, cursor_pos);
+        // Like the finder, the menu counts as part of the editor, so clicking
+        // it doesn't clear the selection.
+        if menu_used {
+            cursor_in_editor = true;
+        }
+
+        /* Mouse input handling */
+
+        // This locks the context, so don't hold on to it for too long.
+        let mouse = &ui.ctx().input(|i| i.pointer.clone());
+
+        if mouse.any_released() {
+            if let Some((_, origin)) = self.connection_in_progress.take() {
+                // Dropping a wire on empty space opens the finder with the
+                // templates that can be connected to it.
+                let connected = delayed_responses
+                    .iter()
+                    .any(|response| matches!(response, NodeResponse::ConnectEventEnded { .. }));
+                if !connected
+                    && cursor_in_editor
+                    && !cursor_over_node
+                    && !cursor_in_finder
+                    && !cursor_in_minimap
+                {
+                    self.open_finder_for_dropped_wire(
+                        origin,
+                        &all_kinds,
+                        editor_rect,
+                        cursor_pos,
+                        user_state,
+                    );
+                }
+            }
+        }
+
+        // Releasing the mouse ends drags and value edits, so further changes
+        // get their own undo step.
+        if mouse.any_released() {
+            self.undo_history.end_group();
+            ui.ctx()
+                .memory_mut(|mem| mem.data.remove::<Vec2>(ui.id().with("snap_remainder")));
+        }
+
+        if mouse.secondary_released()
+            && cursor_in_editor
+            && !cursor_in_finder
+            && !cursor_in_minimap
+            && !show_canvas_menu
+        {
+            self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+        }
+        if ui.ctx().input(|i| i.key_pressed(Key::Escape)) {
+            self.node_finder = None;
+        }
+
+        if r.dragged() && ui.ctx().input(|i| i.pointer.middle_down()) {
+            self.pan_zoom.pan += ui.ctx().input(|i| i.pointer.delta());
+            self.view_animation = None;
+        }
+
+        // Deselect and deactivate finder if the editor backround is clicked,
+        // *or* if the the mouse clicks off the ui
+        if (click_on_background && click_mode == SelectionMode::Replace)
+            || (mouse.any_click() && !cursor_in_editor)
+        {
+            self.selected_nodes = Vec::new();
+            self.selected_frames = Vec::new();
+            self.selected_notes = Vec::new();
+        }
+        if click_on_background || (mouse.any_click() && !cursor_in_editor) {
+            self.node_finder = None;
+        }
+
+        if drag_started_on_background && mouse.primary_down() {
+            self.ongoing_box_selection = Some(cursor_pos);
+            let mode = if modifiers.shift {
+                SelectionMode::Add
+            } else if modifiers.command {
+                SelectionMode::Subtract
+            } else {
+                SelectionMode::Replace
+            };
+            self.box_selection_base = Some(BoxSelectionBase {
+                mode,
+                nodes: self.selected_nodes.clone(),
+                frames: self.selected_frames.clone(),
+                notes: self.selected_notes.clone(),
+            });
+        }
+        if mouse.primary_released() || drag_released_on_background {
+            self.ongoing_box_selection = None;
+            self.box_selection_base = None;
+        }
+
+        GraphResponse {
+            node_responses: delayed_responses,
+            cursor_in_editor,
+            cursor_in_finder,
+        }
+    }
+
+    /// Handles the keyboard shortcuts of the editor, while it has the keyboard
+    /// focus and no text field wants keyboard input. Creating comment frames
+    /// and deleting the selected frames and notes are completed after drawing
+    /// the nodes.
+    #[allow(clippy::too_many_arguments)]
+    fn handle_shortcuts(
+        &mut self,
+        ui: &Ui,
+        has_focus: bool,
+        editor_rect: Rect,
+        cursor_pos: Pos2,
+        snap_to_grid: bool,
+        delayed_responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+        user_state: &mut UserState,
+    ) -> DeferredShortcuts {
+        if !has_focus || ui.ctx().wants_keyboard_input() {
+            return DeferredShortcuts::default();
+        }
+
+        // Navigation shortcuts: F frames the selection, and Home frames the
+        // whole graph.
+        let (frame_selection, frame_all) = ui.ctx().input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::F),
+                i.consume_key(Modifiers::NONE, Key::Home),
+            )
+        });
+        if frame_selection {
+            self.frame_selection();
+        } else if frame_all {
+            self.frame_all();
+        }
+        // Undo / redo shortcuts. Focused text fields handle these keys on
+        // their own, so they are left alone.
+        let (undo, redo) = ui.ctx().input_mut(|i| {
+            (
+                i.consume_key(Modifiers::COMMAND, Key::Z),
+                i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)
+                    || i.consume_key(Modifiers::COMMAND, Key::Y),
+            )
+        });
+        if undo {
+            self.undo();
+        } else if redo {
+            self.redo();
+        }
+
+        // Clipboard shortcuts. Integrations like eframe report ctrl+C/X/V as
+        // copy, cut and paste events rather than key presses.
+        let (copy, cut, paste, duplicate) = ui.ctx().input_mut(|i| {
+            let mut copy = i.consume_key(Modifiers::COMMAND, Key::C);
+            let mut cut = i.consume_key(Modifiers::COMMAND, Key::X);
+            let mut paste = i.consume_key(Modifiers::COMMAND, Key::V).then_some(None);
+            for event in &i.events {
+                match event {
+                    Event::Copy => copy = true,
+                    Event::Cut => cut = true,
+                    Event::Paste(text) => paste = Some(Some(text.clone())),
+                    _ => {}
+                }
+            }
+            let duplicate = i.consume_key(Modifiers::COMMAND, Key::D);
+            (copy, cut, paste, duplicate)
+        });
+
+        if copy || cut {
+            // Cutting only takes the nodes that can be deleted.
+            let nodes: Vec<NodeId> = self
+                .selected_nodes
+                .iter()
+                .copied()
+                .filter(|node_id| {
+                    !cut || self.graph[*node_id].user_data.can_delete(
+                        *node_id,
+                        &self.graph,
+                        user_state,
+                    )
+                })
+                .collect();
+            if !nodes.is_empty() {
+                let clipboard = GraphClipboard::copy(&self.graph, &self.node_positions, &nodes);
+                #[cfg(feature = "persistence")]
+                if let Some(text) = clipboard.to_text() {
+                    ui.ctx().output_mut(|o| o.copied_text = text);
+                }
+                self.clipboard = Some(clipboard);
+            }
+            if cut {
+                delayed_responses.extend(nodes.into_iter().map(NodeResponse::DeleteNodeUi));
+            }
+        }
+
+        if let Some(text) = paste {
+            // Nodes copied from another editor take precedence over the
+            // ones copied in this editor.
+            #[cfg(feature = "persistence")]
+            let pasted = text.as_deref().and_then(GraphClipboard::from_text);
+            #[cfg(not(feature = "persistence"))]
+            let pasted = text.and(None);
+            if let Some(clipboard) = pasted.or_else(|| self.clipboard.clone()) {
+                self.record_undo_step(None);
+                let position = self.pan_zoom.screen_to_graph(editor_rect.min, cursor_pos);
+                let new_nodes = self.paste(&clipboard, position);
+                if snap_to_grid {
+                    self.snap_to_grid(&new_nodes);
+                }
+                delayed_responses.extend(new_nodes.into_iter().map(NodeResponse::CreatedNode));
+            }
+        } else if duplicate && !self.selected_nodes.is_empty() {
+            self.record_undo_step(None);
+            let new_nodes = self.duplicate_selection();
+            if snap_to_grid {
+                self.snap_to_grid(&new_nodes);
+            }
+            delayed_responses.extend(new_nodes.into_iter().map(NodeResponse::CreatedNode));
+        }
+
+        // Annotation shortcuts: C surrounds the selected nodes with a comment
+        // frame, and N adds a sticky note at the cursor.
+        let (add_frame, add_note) = ui.ctx().input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::C),
+                i.consume_key(Modifiers::NONE, Key::N),
+            )
+        });
+        if add_note {
+            self.record_undo_step(None);
+            let position = self.pan_zoom.screen_to_graph(editor_rect.min, cursor_pos);
+            let note = self.add_sticky_note(position, "Note");
+            self.selected_notes = vec![note];
+        }
+
+        // Group the selected nodes with ctrl+G
+        if !self.selected_nodes.is_empty()
+            && ui
+                .ctx()
+                .input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::G))
+        {
+            self.record_undo_step(None);
+            let nodes = std::mem::take(&mut self.selected_nodes);
+            self.create_group(&nodes, "Group");
+        }
+
+        // Lay out the selected nodes, or the whole view, with L
+        if ui
+            .ctx()
+            .input_mut(|i| i.consume_key(Modifiers::NONE, Key::L))
+        {
+            self.tidy_up();
+        }
+
+        // Selection shortcuts: delete or backspace deletes the selection,
+        // ctrl+A selects everything and the arrow keys nudge the selection.
+        let (delete, select_all, nudge) = ui.ctx().input_mut(|i| {
+            let delete = i.consume_key(Modifiers::NONE, Key::Delete)
+                || i.consume_key(Modifiers::NONE, Key::Backspace);
+            let select_all = i.consume_key(Modifiers::COMMAND, Key::A);
+            let nudge = [
+                (Key::ArrowLeft, -Vec2::X),
+                (Key::ArrowRight, Vec2::X),
+                (Key::ArrowUp, -Vec2::Y),
+                (Key::ArrowDown, Vec2::Y),
+            ]
+            .into_iter()
+            .fold(Vec2::ZERO, |nudge, (key, direction)| {
+                if i.consume_key(Modifiers::SHIFT, key) {
+                    nudge + direction * NUDGE_DISTANCE_LARGE
+                } else if i.consume_key(Modifiers::NONE, key) {
+                    nudge + direction * NUDGE_DISTANCE
+                } else {
+                    nudge
+                }
+            });
+            (delete, select_all, nudge)
+        });
+
+        if delete {
+            for node_id in self.selected_nodes.iter().copied() {
+                if self.graph[node_id]
+                    .user_data
+                    .can_delete(node_id, &self.graph, user_state)
+                {
+                    delayed_responses.push(NodeResponse::DeleteNodeUi(node_id));
+                }
+            }
+        }
+        if select_all {
+            // Nodes that are not drawn are deselected below.
+            self.selected_nodes = self.node_order.clone();
+            self.selected_frames = self.frames.keys().collect();
+            self.selected_notes = self.notes.keys().collect();
+        }
+        let has_selection = !self.selected_nodes.is_empty()
+            || !self.selected_frames.is_empty()
+            || !self.selected_notes.is_empty();
+        if nudge != Vec2::ZERO && has_selection {
+            self.record_undo_step(Some(UndoGroup::NudgeSelection));
+            for node_id in self.selected_nodes.iter().copied() {
+                self.node_positions[node_id] += nudge;
+            }
+            for frame in self.selected_frames.iter().copied() {
+                self.frames[frame].rect = self.frames[frame].rect.translate(nudge);
+            }
+            for note in self.selected_notes.iter().copied() {
+                self.notes[note].position += nudge;
+            }
+        }
+
+        DeferredShortcuts {
+            add_frame,
+            delete_selection: delete,
+        }
+    }
+
+    /// Right-clicking the background with several nodes selected opens a menu
+    /// to arrange them, instead of the node finder. Returns whether the menu
+    /// can be opened, and whether one of its commands was chosen.
+    fn canvas_context_menu(&mut self, background: &Response, cursor_pos: Pos2) -> (bool, bool) {
+        let show_canvas_menu = self.selected_nodes.len() >= 2;
+        let mut canvas_action = None;
+        if show_canvas_menu {
+            background
+                .clone()
+                .context_menu(|ui| canvas_action = canvas_menu(ui));
+        }
+        let menu_used = canvas_action.is_some();
+        match canvas_action {
+            Some(CanvasAction::AddNode) => {
+                self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+            }
+            Some(CanvasAction::Align(alignment)) => self.align_selection(alignment),
+            Some(CanvasAction::Distribute(distribution)) => self.distribute_selection(distribution),
+            Some(CanvasAction::TidyUp) => self.tidy_up(),
+            None => {}
+        }
+        (show_canvas_menu, menu_used)
+    }
+
+    /// Draws the node finder, when open, and adds the node submitted from it.
+    /// Nodes created from a dropped wire are connected to its origin. Returns
+    /// whether the cursor is over the finder.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_node_finder(
+        &mut self,
+        ui: &Ui,
+        background: &Response,
+        all_kinds: &impl NodeTemplateIter<Item = NodeTemplate>,
+        editor_rect: Rect,
+        cursor_pos: Pos2,
+        snap_to_grid: bool,
+        style: &GraphStyle,
+        delayed_responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+        extra_responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+        user_state: &mut UserState,
+    ) -> bool {
+        let mut cursor_in_finder = false;
+        let mut submitted_node_kind = None;
         if let Some(ref mut node_finder) = self.node_finder {
             let mut node_finder_area = Area::new("node_finder").order(Order::Foreground);
             if let Some(pos) = node_finder.position {
                 node_finder_area = node_finder_area.current_pos(pos);
             }
+            let kinds = node_finder
+                .compatible_kinds
+                .clone()
+                .unwrap_or_else(|| all_kinds.all_kinds());
             node_finder_area.show(ui.ctx(), |ui| {
-                if let Some(node_kind) = node_finder.show(ui, all_kinds, user_state) {
-                    let new_node = self.graph.add_node(
-                        node_kind.node_graph_label(user_state),
-                        node_kind.user_data(user_state),
-                        |graph, node_id| node_kind.build_node(graph, user_state, node_id),
-                    );
-                    self.node_positions.insert(
-                        new_node,
-                        cursor_pos - self.pan_zoom.pan - editor_rect.min.to_vec2(),
-                    );
-                    self.node_order.push(new_node);
-
-                    should_close_node_finder = true;
-                    delayed_responses.push(NodeResponse::CreatedNode(new_node));
-                }
-                let finder_rect = ui.min_rect();
-                // If the cursor is not in the main editor, check if the cursor is in the finder
-                // if the cursor is in the finder, then we can consider that also in the editor.
-                if finder_rect.contains(cursor_pos) {
-                    cursor_in_editor = true;
-                    cursor_in_finder = true;
-                }
+                submitted_node_kind = node_finder.show(
+                    ui,
+                    kinds,
+                    style,
+                    &mut self.node_finder_settings,
+                    &mut self.template_ports,
+                    user_state,
+                );
+                cursor_in_finder = ui.min_rect().contains(cursor_pos);
             });
         }
-        if should_close_node_finder {
-            self.node_finder = None;
+        if let Some(node_kind) = submitted_node_kind {
+            self.record_undo_step(None);
+            let finder = self.node_finder.take();
+            // The search field of the finder had the keyboard focus.
+            background.request_focus();
+            let new_node = self.graph.add_node(
+                node_kind.node_graph_label(user_state),
+                node_kind.user_data(user_state),
+                |graph, node_id| node_kind.build_node(graph, user_state, node_id),
+            );
+            // Nodes created from a dropped wire are placed where it was
+            // dropped, and the others where the finder was clicked.
+            let position = finder
+                .as_ref()
+                .and_then(|finder| finder.drop_position)
+                .unwrap_or_else(|| self.pan_zoom.screen_to_graph(editor_rect.min, cursor_pos));
+            self.node_positions.insert(new_node, position);
+            self.node_order.push(new_node);
+            self.add_to_open_group(&[new_node]);
+            if snap_to_grid {
+                self.snap_to_grid(&[new_node]);
+            }
+
+            let wire_origin = finder.and_then(|finder| finder.wire_origin);
+            delayed_responses.push(NodeResponse::CreatedNode(new_node));
+
+            // Nodes created from a dropped wire get connected to its origin,
+            // in the same undo step.
+            if let Some((output, input)) = wire_origin.and_then(|origin| {
+                wire_drop_connection(
+                    &self.graph,
+                    new_node,
+                    origin,
+                    self.reject_cycles,
+                    user_state,
+                )
+            }) {
+                self.graph.add_connection(output, input);
+                extra_responses.push(NodeResponse::ConnectEventEnded { output, input });
+            }
         }
+        cursor_in_finder
+    }
 
-        /* Draw connections */
-        if let Some((_, ref locator)) = self.connection_in_progress {
+    /// Draws the wire being dragged from a port, snapped to the closest port it
+    /// can be connected to. Returns whether a wire is being dragged.
+    fn draw_dragged_wire(
+        &self,
+        ui: &Ui,
+        cursor_pos: Pos2,
+        style: &GraphStyle,
+        port_locations: &PortLocations,
+        cycle_guard: Option<&CycleGuard>,
+        user_state: &mut UserState,
+    ) -> bool {
+        let zoom = self.pan_zoom.zoom;
+        // The origin port may have been hidden by collapsing its group.
+        let drag_origin = self
+            .connection_in_progress
+            .filter(|(_, locator)| port_locations.contains_key(locator));
+        if let Some((origin_node, ref locator)) = drag_origin {
             let port_type = self.graph.any_param_type(*locator).unwrap();
-            let connection_color = port_type.data_type_color(user_state);
             let start_pos = port_locations[locator];
 
             // Find a port to connect to
-            fn snap_to_ports<
-                NodeData,
-                UserState,
-                DataType: DataTypeTrait<UserState>,
-                ValueType,
-                Key: slotmap::Key + Into<AnyParameterId>,
-                Value,
-            >(
-                graph: &Graph<NodeData, DataType, ValueType>,
-                port_type: &DataType,
+            fn snap_to_ports<Key: slotmap::Key + Into<AnyParameterId>, Value>(
                 ports: &SlotMap<Key, Value>,
                 port_locations: &PortLocations,
                 cursor_pos: Pos2,
+                zoom: f32,
+                mut is_compatible: impl FnMut(Key) -> bool,
             ) -> Pos2 {
                 ports
                     .iter()
                     .find_map(|(port_id, _)| {
-                        let compatible_ports = graph
-                            .any_param_type(port_id.into())
-                            .map(|other| other == port_type)
-                            .unwrap_or(false);
-
-                        if compatible_ports {
+                        if is_compatible(port_id) {
                             port_locations.get(&port_id.into()).and_then(|port_pos| {
-                                if port_pos.distance(cursor_pos) < DISTANCE_TO_CONNECT {
+                                if port_pos.distance(cursor_pos) < DISTANCE_TO_CONNECT * zoom {
                                     Some(*port_pos)
                                 } else {
                                     None
@@ -259,49 +922,76 @@ where
                     .unwrap_or(cursor_pos)
             }
 
-            let (src_pos, dst_pos) = match locator {
-                AnyParameterId::Output(_) => (
+            // The merged ports of collapsed nodes are not snapped to
+            let graph = &self.graph;
+            let collapsed_nodes = &self.collapsed_nodes;
+            let (src_pos, dst_pos) = match *locator {
+                AnyParameterId::Output(output) => (
                     start_pos,
-                    snap_to_ports(
-                        &self.graph,
-                        port_type,
-                        &self.graph.inputs,
-                        &port_locations,
-                        cursor_pos,
-                    ),
+                    snap_to_ports(&graph.inputs, port_locations, cursor_pos, zoom, |input| {
+                        graph[input].node != origin_node
+                            && !collapsed_nodes.contains_key(graph[input].node)
+                            && drag_compatibility(graph, output, input, cycle_guard, user_state)
+                                .is_compatible()
+                    }),
                 ),
-                AnyParameterId::Input(_) => (
-                    snap_to_ports(
-                        &self.graph,
-                        port_type,
-                        &self.graph.outputs,
-                        &port_locations,
-                        cursor_pos,
-                    ),
+                AnyParameterId::Input(input) => (
+                    snap_to_ports(&graph.outputs, port_locations, cursor_pos, zoom, |output| {
+                        graph[output].node != origin_node
+                            && !collapsed_nodes.contains_key(graph[output].node)
+                            && drag_compatibility(graph, output, input, cycle_guard, user_state)
+                                .is_compatible()
+                    }),
                     start_pos,
                 ),
             };
-            draw_connection(ui.painter(), src_pos, dst_pos, connection_color);
+            let wire = Wire {
+                points: vec![src_pos, dst_pos],
+                path: wire_path(self.wire_style, src_pos, dst_pos, zoom),
+                stroke: port_type.wire_stroke(None, style, user_state),
+                highlight: WireHighlight::None,
+                selection_color: style.wire_selection,
+                zoom,
+            };
+            port_type.paint_wire(ui.painter(), &wire, user_state);
         }
+        drag_origin.is_some()
+    }
 
-        for (input, output) in self.graph.iter_connections() {
-            let port_type = self
-                .graph
-                .any_param_type(AnyParameterId::Output(output))
-                .unwrap();
-            let connection_color = port_type.data_type_color(user_state);
-            let src_pos = port_locations[&AnyParameterId::Output(output)];
-            let dst_pos = port_locations[&AnyParameterId::Input(input)];
-            draw_connection(ui.painter(), src_pos, dst_pos, connection_color);
+    /// Applies the responses returned from drawing the nodes to the editor
+    /// state, after recording them as a single undo step. Responses generated
+    /// while doing so are pushed to `extra_responses`.
+    fn handle_node_responses(
+        &mut self,
+        ui: &Ui,
+        responses: &[NodeResponse<UserResponse, NodeData>],
+        click_mode: SelectionMode,
+        snap_to_grid: bool,
+        extra_responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+        user_state: &mut UserState,
+    ) {
+        // All the graph changes of this frame become a single undo step.
+        // Frames that only move nodes are merged while the drag goes on.
+        let mut changes = responses.iter().filter_map(|response| match response {
+            NodeResponse::MoveNode { .. } | NodeResponse::ResizeNode { .. } => {
+                Some(Some(UndoGroup::MoveNodes))
+            }
+            NodeResponse::ConnectEventEnded { .. }
+            | NodeResponse::DeleteNodeUi(_)
+            | NodeResponse::DisconnectEvent { .. }
+            | NodeResponse::CollapseNode { .. } => Some(None),
+            _ => None,
+        });
+        if let Some(first) = changes.next() {
+            let group = if changes.all(|group| group == first) {
+                first
+            } else {
+                None
+            };
+            self.record_undo_step(group);
         }
 
-        /* Handle responses from drawing nodes */
-
-        // Some responses generate additional responses when processed. These
-        // are stored here to report them back to the user.
-        let mut extra_responses: Vec<NodeResponse<UserResponse, NodeData>> = Vec::new();
-
-        for response in delayed_responses.iter() {
+        for response in responses.iter() {
             match response {
                 NodeResponse::ConnectEventStarted(node_id, port) => {
                     self.connection_in_progress = Some((*node_id, *port));
@@ -313,7 +1003,12 @@ where
                     //Convenience NodeResponse for users
                 }
                 NodeResponse::SelectNode(node_id) => {
-                    self.selected_nodes = Vec::from([*node_id]);
+                    if click_mode == SelectionMode::Replace {
+                        self.selected_connection = None;
+                        self.selected_frames.clear();
+                        self.selected_notes.clear();
+                    }
+                    self.selected_nodes = click_mode.combine(&self.selected_nodes, [*node_id]);
                 }
                 NodeResponse::DeleteNodeUi(node_id) => {
                     let (node, disc_events) = self.graph.remove_node(*node_id);
@@ -331,13 +1026,17 @@ where
                         node,
                     });
                     self.node_positions.remove(*node_id);
+                    self.node_sizes.remove(*node_id);
+                    self.custom_node_sizes.remove(*node_id);
+                    self.collapsed_nodes.remove(*node_id);
                     // Make sure to not leave references to old nodes hanging
                     self.selected_nodes.retain(|id| *id != *node_id);
                     self.node_order.retain(|id| *id != *node_id);
+                    self.prune_groups();
                 }
                 NodeResponse::DisconnectEvent { input, output } => {
                     let other_node = self.graph.get_output(*output).node;
-                    self.graph.remove_connection(*input);
+                    self.graph.remove_single_connection(*output, *input);
                     self.connection_in_progress =
                         Some((other_node, AnyParameterId::Output(*output)));
                 }
@@ -351,15 +1050,53 @@ where
                     self.node_order.push(*node_id);
                 }
                 NodeResponse::MoveNode { node, drag_delta } => {
-                    self.node_positions[*node] += *drag_delta;
+                    let mut drag_delta = *drag_delta;
+                    if snap_to_grid {
+                        // The dragged node jumps between grid points. The part
+                        // of the drag that was not applied is remembered, so
+                        // that slow drags still reach the next grid point.
+                        let remainder_id = ui.id().with("snap_remainder");
+                        let remainder: Vec2 = ui
+                            .ctx()
+                            .memory_mut(|mem| mem.data.get_temp(remainder_id))
+                            .unwrap_or_default();
+                        let target = self.node_positions[*node] + remainder + drag_delta;
+                        drag_delta = self.grid.snap_pos(target) - self.node_positions[*node];
+                        ui.ctx().memory_mut(|mem| {
+                            mem.data
+                                .insert_temp(remainder_id, target - self.grid.snap_pos(target))
+                        });
+                    }
+                    self.node_positions[*node] += drag_delta;
                     // Handle multi-node selection movement
                     if self.selected_nodes.contains(node) && self.selected_nodes.len() > 1 {
                         for n in self.selected_nodes.iter().copied() {
                             if n != *node {
-                                self.node_positions[n] += *drag_delta;
+                                self.node_positions[n] += drag_delta;
                             }
                         }
                     }
+                    // Selected frames and notes follow the selected nodes
+                    if self.selected_nodes.contains(node) {
+                        for frame in self.selected_frames.iter().copied() {
+                            self.frames[frame].rect = self.frames[frame].rect.translate(drag_delta);
+                        }
+                        for note in self.selected_notes.iter().copied() {
+                            self.notes[note].position += drag_delta;
+                        }
+                    }
+                }
+                NodeResponse::ResizeNode { node, size } => {
+                    let constraints = self.graph[*node].user_data.size_constraints(
+                        *node,
+                        &self.graph,
+                        user_state,
+                    );
+                    self.custom_node_sizes
+                        .insert(*node, constraints.clamp(*size));
+                }
+                NodeResponse::CollapseNode { node, collapsed } => {
+                    self.set_node_collapsed(*node, *collapsed);
                 }
                 NodeResponse::User(_) => {
                     // These are handled by the user code.
@@ -369,97 +1106,311 @@ where
                 }
             }
         }
+    }
 
-        // Handle box selection
+    /// Draws the ongoing box selection, and selects the nodes and notes it
+    /// touches and the frames it contains.
+    fn update_box_selection(
+        &mut self,
+        ui: &Ui,
+        cursor_pos: Pos2,
+        style: &GraphStyle,
+        node_rects: NodeRects,
+        annotation_rects: AnnotationRects,
+    ) {
         if let Some(box_start) = self.ongoing_box_selection {
             let selection_rect = Rect::from_two_pos(cursor_pos, box_start);
-            let bg_color = Color32::from_rgba_unmultiplied(200, 200, 200, 20);
-            let stroke_color = Color32::from_rgba_unmultiplied(200, 200, 200, 180);
             ui.painter().rect(
                 selection_rect,
                 2.0,
-                bg_color,
-                Stroke::new(3.0, stroke_color),
+                style.box_selection_fill,
+                style.box_selection_stroke,
             );
 
-            self.selected_nodes = node_rects
-                .into_iter()
-                .filter_map(|(node_id, rect)| {
+            let base = self.box_selection_base.take().unwrap_or_default();
+            self.selected_nodes = base.mode.combine(
+                &base.nodes,
+                node_rects.into_iter().filter_map(|(node_id, rect)| {
                     if selection_rect.intersects(rect) {
                         Some(node_id)
                     } else {
                         None
                     }
+                }),
+            );
+            // Frames are only selected when fully inside the box, otherwise
+            // selecting the nodes inside a frame would select the frame too.
+            self.selected_frames = base.mode.combine(
+                &base.frames,
+                annotation_rects
+                    .frames
+                    .into_iter()
+                    .filter(|(_, rect)| selection_rect.contains_rect(*rect))
+                    .map(|(frame_id, _)| frame_id),
+            );
+            self.selected_notes = base.mode.combine(
+                &base.notes,
+                annotation_rects
+                    .notes
+                    .into_iter()
+                    .filter(|(_, rect)| selection_rect.intersects(*rect))
+                    .map(|(note_id, _)| note_id),
+            );
+            self.box_selection_base = Some(base);
+        }
+    }
+
+    /// Opens the node finder with the templates that can be connected to a
+    /// wire dragged from `origin` and dropped at `cursor_pos`.
+    fn open_finder_for_dropped_wire(
+        &mut self,
+        origin: AnyParameterId,
+        all_kinds: &impl NodeTemplateIter<Item = NodeTemplate>,
+        editor_rect: Rect,
+        cursor_pos: Pos2,
+        user_state: &mut UserState,
+    ) {
+        if let Ok(typ) = self.graph.any_param_type(origin) {
+            let from_output = matches!(origin, AnyParameterId::Output(_));
+            let kinds = all_kinds
+                .all_kinds()
+                .into_iter()
+                .filter(|kind| {
+                    self.template_ports
+                        .get(kind, user_state)
+                        .accepts(typ, from_output, user_state)
                 })
                 .collect();
+            let drop_position = self.pan_zoom.screen_to_graph(editor_rect.min, cursor_pos);
+            self.node_finder = Some(NodeFinder::new_for_wire(
+                cursor_pos,
+                origin,
+                drop_position,
+                kinds,
+            ));
         }
+    }
+}
 
-        // Push any responses that were generated during response handling.
-        // These are only informative for the end-user and need no special
-        // treatment here.
-        delayed_responses.extend(extra_responses);
-
-        /* Mouse input handling */
+/// Shortcuts that are completed after drawing the nodes.
+#[derive(Default)]
+struct DeferredShortcuts {
+    /// Surround the selected nodes with a comment frame.
+    add_frame: bool,
+    /// Delete the selected frames and notes, along with the nodes.
+    delete_selection: bool,
+}
 
-        // This locks the context, so don't hold on to it for too long.
-        let mouse = &ui.ctx().input(|i| i.pointer.clone());
+/// Commands of the context menu of the canvas.
+enum CanvasAction {
+    AddNode,
+    Align(Alignment),
+    Distribute(Distribution),
+    TidyUp,
+}
 
-        if mouse.any_released() && self.connection_in_progress.is_some() {
-            self.connection_in_progress = None;
+/// Shows the entries of the context menu of the canvas. Returns the chosen
+/// command, if any.
+fn canvas_menu(ui: &mut Ui) -> Option<CanvasAction> {
+    let mut action = None;
+    if ui.button("Add node…").clicked() {
+        action = Some(CanvasAction::AddNode);
+    }
+    ui.menu_button("Align", |ui| {
+        for (label, alignment) in [
+            ("Left", Alignment::Left),
+            ("Center", Alignment::CenterHorizontal),
+            ("Right", Alignment::Right),
+            ("Top", Alignment::Top),
+            ("Middle", Alignment::CenterVertical),
+            ("Bottom", Alignment::Bottom),
+        ] {
+            if ui.button(label).clicked() {
+                action = Some(CanvasAction::Align(alignment));
+            }
         }
-
-        if mouse.secondary_released() && cursor_in_editor && !cursor_in_finder {
-            self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+    });
+    ui.menu_button("Distribute", |ui| {
+        if ui.button("Horizontally").clicked() {
+            action = Some(CanvasAction::Distribute(Distribution::Horizontal));
         }
-        if ui.ctx().input(|i| i.key_pressed(Key::Escape)) {
-            self.node_finder = None;
+        if ui.button("Vertically").clicked() {
+            action = Some(CanvasAction::Distribute(Distribution::Vertical));
         }
+    });
+    if ui.button("Tidy up").clicked() {
+        action = Some(CanvasAction::TidyUp);
+    }
+    if action.is_some() {
+        ui.close_menu();
+    }
+    action
+}
 
-        if r.dragged() && ui.ctx().input(|i| i.pointer.middle_down()) {
-            self.pan_zoom.pan += ui.ctx().input(|i| i.pointer.delta());
-        }
+/// The nodes a wire dragged from a port can't be connected to without
+/// creating a cycle. Computed once per frame while dragging, when
+/// [`GraphEditorState::reject_cycles`] is set, so checking a port is cheap.
+pub struct CycleGuard {
+    origin: AnyParameterId,
+    nodes: SecondaryMap<NodeId, ()>,
+}
 
-        // Deselect and deactivate finder if the editor backround is clicked,
-        // *or* if the the mouse clicks off the ui
-        if click_on_background || (mouse.any_click() && !cursor_in_editor) {
-            self.selected_nodes = Vec::new();
-            self.node_finder = None;
-        }
+impl CycleGuard {
+    pub fn new<NodeData, DataType, ValueType>(
+        graph: &Graph<NodeData, DataType, ValueType>,
+        origin_node: NodeId,
+        origin: AnyParameterId,
+    ) -> Self {
+        let nodes = match origin {
+            AnyParameterId::Output(_) => graph.upstream_nodes(origin_node),
+            AnyParameterId::Input(_) => graph.downstream_nodes(origin_node),
+        };
+        Self { origin, nodes }
+    }
 
-        if drag_started_on_background && mouse.primary_down() {
-            self.ongoing_box_selection = Some(cursor_pos);
-        }
-        if mouse.primary_released() || drag_released_on_background {
-            self.ongoing_box_selection = None;
-        }
+    /// Whether connecting `output` to `input`, one of them being the origin
+    /// of the wire, would create a cycle.
+    pub fn closes_cycle<NodeData, DataType, ValueType>(
+        &self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        output: OutputId,
+        input: InputId,
+    ) -> bool {
+        let other = match self.origin {
+            AnyParameterId::Output(_) => graph[input].node,
+            AnyParameterId::Input(_) => graph[output].node,
+        };
+        self.nodes.contains_key(other)
+    }
+}
 
-        GraphResponse {
-            node_responses: delayed_responses,
-            cursor_in_editor,
-            cursor_in_finder,
-        }
+/// Whether a wire being dragged by the user can connect `output` to `input`.
+/// Besides the data types, this rejects inputs that can't take any more
+/// connections, existing connections, and connections closing a cycle when a
+/// `cycle_guard` is given.
+fn drag_compatibility<NodeData, DataType, ValueType, UserState>(
+    graph: &Graph<NodeData, DataType, ValueType>,
+    output: OutputId,
+    input: InputId,
+    cycle_guard: Option<&CycleGuard>,
+    user_state: &mut UserState,
+) -> ConnectionCompatibility
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    if graph.is_input_full(input)
+        || graph.connections(input).contains(&output)
+        || cycle_guard.is_some_and(|guard| guard.closes_cycle(graph, output, input))
+    {
+        ConnectionCompatibility::Incompatible
+    } else {
+        graph.connection_compatibility(output, input, user_state)
     }
 }
 
-fn draw_connection(painter: &Painter, src_pos: Pos2, dst_pos: Pos2, color: Color32) {
-    let connection_stroke = egui::Stroke { width: 5.0, color };
+/// The first port of `node_id` that a wire dragged from `origin` can be
+/// connected to, as an (output, input) pair.
+fn wire_drop_connection<NodeData, DataType, ValueType, UserState>(
+    graph: &Graph<NodeData, DataType, ValueType>,
+    node_id: NodeId,
+    origin: AnyParameterId,
+    reject_cycles: bool,
+    user_state: &mut UserState,
+) -> Option<(OutputId, InputId)>
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    // The origin may have been deleted while the finder was open.
+    graph.any_param_type(origin).ok()?;
+    let origin_node = match origin {
+        AnyParameterId::Output(output) => graph[output].node,
+        AnyParameterId::Input(input) => graph[input].node,
+    };
+    let cycle_guard = reject_cycles.then(|| CycleGuard::new(graph, origin_node, origin));
+    let cycle_guard = cycle_guard.as_ref();
+    match origin {
+        AnyParameterId::Output(output) => graph[node_id]
+            .input_ids()
+            .find(|input| {
+                !matches!(graph[*input].kind, InputParamKind::ConstantOnly)
+                    && drag_compatibility(graph, output, *input, cycle_guard, user_state)
+                        .is_compatible()
+            })
+            .map(|input| (output, input)),
+        AnyParameterId::Input(input) => graph[node_id]
+            .output_ids()
+            .find(|output| {
+                drag_compatibility(graph, *output, input, cycle_guard, user_state).is_compatible()
+            })
+            .map(|output| (output, input)),
+    }
+}
 
-    let control_scale = ((dst_pos.x - src_pos.x) / 2.0).max(30.0);
-    let src_control = src_pos + Vec2::X * control_scale;
-    let dst_control = dst_pos - Vec2::X * control_scale;
+/// What is remembered about an inline value between frames, to only clone it
+/// for undo when one of its widgets may change it.
+#[derive(Clone, Copy, Default)]
+struct InlineValueState {
+    /// Where the value was drawn on the last frame.
+    rect: Option<Rect>,
+    /// The widget of the value that last took the keyboard focus.
+    focus: Option<Id>,
+}
+
+impl InlineValueState {
+    fn load(ctx: &Context, id: Id) -> Self {
+        ctx.data(|data| data.get_temp(id)).unwrap_or_default()
+    }
 
-    let bezier = CubicBezierShape::from_points_stroke(
-        [src_pos, src_control, dst_control, dst_pos],
-        false,
-        Color32::TRANSPARENT,
-        connection_stroke,
-    );
+    fn store(self, ctx: &Context, id: Id) {
+        ctx.data_mut(|data| data.insert_temp(id, self));
+    }
 
-    painter.add(bezier);
+    /// Values change when their widgets are clicked, dragged or scrolled,
+    /// or receive keys while focused.
+    fn may_change(&self, ctx: &Context) -> bool {
+        let focused = self.focus.is_some() && ctx.memory(|mem| mem.focus()) == self.focus;
+        ctx.input(|i| {
+            let in_rect = |pos: Option<Pos2>| {
+                pos.zip(self.rect)
+                    .is_some_and(|(pos, rect)| rect.contains(pos))
+            };
+            let pointer_edit = (i.pointer.any_down() || i.pointer.any_released())
+                && in_rect(i.pointer.press_origin());
+            let scroll_edit = i.scroll_delta != Vec2::ZERO && in_rect(i.pointer.hover_pos());
+            let key_edit = focused
+                && i.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        Event::Key { .. }
+                            | Event::Text(_)
+                            | Event::Paste(_)
+                            | Event::Cut
+                            | Event::CompositionEnd(_)
+                    )
+                });
+            pointer_edit || scroll_edit || key_edit
+        })
+    }
+}
+
+/// The position where the wire of the `index`-th connection of an input
+/// enters its port. Inputs with several connections fan their wires
+/// vertically, centered around the port position.
+pub(crate) fn fanned_wire_pos(port_pos: Pos2, index: usize, count: usize, zoom: f32) -> Pos2 {
+    let offset = index as f32 - (count.max(1) - 1) as f32 / 2.0;
+    port_pos + Vec2::Y * offset * WIRE_FAN_SPACING * zoom
+}
+
+/// Returns the size of a node after dragging its resize handle by
+/// `drag_delta`. Nodes that were resized before start from their `size`, and
+/// the others from their measured `outer_size`. The drawn node is taller than
+/// its outer rect, so its drawn size would grow the node on every drag.
+fn resized_node_size(size: Option<Vec2>, outer_size: Vec2, drag_delta: Vec2) -> Vec2 {
+    size.unwrap_or(outer_size) + drag_delta
 }
 
 #[derive(Clone, Copy, Debug)]
-struct OuterRectMemory(Rect);
+pub(crate) struct OuterRectMemory(pub(crate) Rect);
 
 impl<'a, NodeData, DataType, ValueType, UserResponse, UserState>
     GraphNodeWidget<'a, NodeData, DataType, ValueType>
@@ -471,24 +1422,44 @@ where
         ValueType = ValueType,
     >,
     UserResponse: UserResponseTrait,
-    ValueType:
-        WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>,
+    ValueType: WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>
+        + Clone
+        + PartialEq,
     DataType: DataTypeTrait<UserState>,
 {
-    pub const MAX_NODE_SIZE: [f32; 2] = [200.0, 200.0];
-
     pub fn show(
         self,
         ui: &mut Ui,
         user_state: &mut UserState,
     ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let constraints = self.graph[self.node_id].user_data.size_constraints(
+            self.node_id,
+            self.graph,
+            user_state,
+        );
+        // Resized nodes are drawn at their size, others fit their contents.
+        // Collapsed nodes only fit their title.
+        let (max_size, min_size) = match self.size {
+            _ if self.collapsed => (constraints.fit, Vec2::ZERO),
+            Some(size) => {
+                let size = constraints.clamp(size);
+                (size, size)
+            }
+            None => (constraints.fit.max(constraints.min), constraints.min),
+        };
         let mut child_ui = ui.child_ui_with_id_source(
-            Rect::from_min_size(*self.position + self.pan, Self::MAX_NODE_SIZE.into()),
+            Rect::from_min_size(
+                (self.position.to_vec2() * self.zoom + self.pan).to_pos2(),
+                max_size * self.zoom,
+            ),
             Layout::default(),
             self.node_id,
         );
+        // Node contents are drawn with a scaled copy of the style, so text,
+        // spacing and widgets follow the zoom level.
+        child_ui.style_mut().scale(self.zoom);
 
-        Self::show_graph_node(self, &mut child_ui, user_state)
+        Self::show_graph_node(self, &mut child_ui, min_size, user_state)
     }
 
     /// Draws this node. Also fills in the list of port locations with all of its ports.
@@ -496,22 +1467,15 @@ where
     fn show_graph_node(
         self,
         ui: &mut Ui,
+        min_size: Vec2,
         user_state: &mut UserState,
     ) -> Vec<NodeResponse<UserResponse, NodeData>> {
-        let margin = egui::vec2(15.0, 5.0);
+        let style = self.style;
+        let margin = style.node_margin * self.zoom;
         let mut responses = Vec::<NodeResponse<UserResponse, NodeData>>::new();
 
-        let background_color;
-        let text_color;
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#ffffff").unwrap();
-            text_color = color_from_hex("#505050").unwrap();
-        }
-
-        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, text_color);
+        ui.visuals_mut().widgets.noninteractive.fg_stroke =
+            Stroke::new(2.0 * self.zoom, style.node_text);
 
         // Preallocate shapes to paint below contents
         let outline_shape = ui.painter().add(Shape::Noop);
@@ -550,20 +1514,40 @@ where
         let mut output_port_heights = vec![];
 
         child_ui.vertical(|ui| {
+            ui.set_min_size((min_size * self.zoom - 2.0 * margin).max(Vec2::ZERO));
             ui.horizontal(|ui| {
+                let (icon, hover_text) = if self.collapsed {
+                    ("⏵", "Expand")
+                } else {
+                    ("⏷", "Collapse")
+                };
+                if ui
+                    .add(Button::new(icon).small().frame(false))
+                    .on_hover_text(hover_text)
+                    .clicked()
+                {
+                    responses.push(NodeResponse::CollapseNode {
+                        node: self.node_id,
+                        collapsed: !self.collapsed,
+                    });
+                }
                 ui.add(Label::new(
                     RichText::new(&self.graph[self.node_id].label)
-                        .text_style(TextStyle::Button)
-                        .color(text_color),
+                        .text_style(style.title_text_style.clone())
+                        .color(style.node_text),
                 ));
-                responses.extend(
-                    self.graph[self.node_id]
-                        .user_data
-                        .top_bar_ui(ui, self.node_id, self.graph, user_state)
-                        .into_iter(),
-                );
-                ui.add_space(8.0); // The size of the little cross icon
+                responses.extend(self.graph[self.node_id].user_data.top_bar_ui(
+                    ui,
+                    self.node_id,
+                    self.graph,
+                    user_state,
+                ));
+                ui.add_space(8.0 * self.zoom); // The size of the little cross icon
             });
+            if self.collapsed {
+                title_height = ui.min_size().y;
+                return;
+            }
             ui.add_space(margin.y);
             title_height = ui.min_size().y;
 
@@ -580,6 +1564,10 @@ where
                     // dummy value. This requires `ValueType` to implement
                     // Default, but results in a totally safe alternative.
                     let mut value = std::mem::take(&mut self.graph[param_id].value);
+                    let value_id = ui.id().with(param_id);
+                    let mut value_state = InlineValueState::load(ui.ctx(), value_id);
+                    let old_value = value_state.may_change(ui.ctx()).then(|| value.clone());
+                    let focus_before = ui.memory(|mem| mem.focus());
 
                     if self.graph.connection(param_id).is_some() {
                         let node_responses = value.value_widget_connected(
@@ -611,9 +1599,23 @@ where
                         user_state,
                     );
 
+                    if let Some(old_value) = old_value {
+                        if old_value != value {
+                            self.value_edits.push((param_id, old_value));
+                        }
+                    }
                     self.graph[param_id].value = value;
 
                     let height_after = ui.min_rect().bottom();
+                    let focus_after = ui.memory(|mem| mem.focus());
+                    if focus_after != focus_before {
+                        value_state.focus = focus_after;
+                    }
+                    value_state.rect = Some(Rect::from_x_y_ranges(
+                        ui.max_rect().x_range(),
+                        height_before..=height_after,
+                    ));
+                    value_state.store(ui.ctx(), value_id);
                     input_port_heights.push((height_before + height_after) / 2.0);
                 }
             }
@@ -621,12 +1623,13 @@ where
             let outputs = self.graph[self.node_id].outputs.clone();
             for (param_name, param_id) in outputs {
                 let height_before = ui.min_rect().bottom();
-                responses.extend(
-                    self.graph[self.node_id]
-                        .user_data
-                        .output_ui(ui, self.node_id, self.graph, user_state, &param_name)
-                        .into_iter(),
-                );
+                responses.extend(self.graph[self.node_id].user_data.output_ui(
+                    ui,
+                    self.node_id,
+                    self.graph,
+                    user_state,
+                    &param_name,
+                ));
 
                 self.graph[self.node_id].user_data.separator(
                     ui,
@@ -640,12 +1643,12 @@ where
                 output_port_heights.push((height_before + height_after) / 2.0);
             }
 
-            responses.extend(
-                self.graph[self.node_id]
-                    .user_data
-                    .bottom_ui(ui, self.node_id, self.graph, user_state)
-                    .into_iter(),
-            );
+            responses.extend(self.graph[self.node_id].user_data.bottom_ui(
+                ui,
+                self.node_id,
+                self.graph,
+                user_state,
+            ));
         });
 
         // Second pass, iterate again to draw the ports. This happens outside
@@ -672,7 +1675,9 @@ where
             param_id: AnyParameterId,
             port_locations: &mut PortLocations,
             ongoing_drag: Option<(NodeId, AnyParameterId)>,
-            is_connected_input: bool,
+            zoom: f32,
+            style: &GraphStyle,
+            cycle_guard: Option<&CycleGuard>,
         ) where
             DataType: DataTypeTrait<UserState>,
             UserResponse: UserResponseTrait,
@@ -680,7 +1685,25 @@ where
         {
             let port_type = graph.any_param_type(param_id).unwrap();
 
-            let port_rect = Rect::from_center_size(port_pos, egui::vec2(10.0, 10.0));
+            // Wide inputs are drawn as a pill, tall enough to fit the fanned wires.
+            let (connections, is_wide) = match param_id {
+                AnyParameterId::Input(input) => (
+                    graph.connections(input),
+                    graph[input].max_connections != NonZeroU32::new(1),
+                ),
+                AnyParameterId::Output(_) => (&[][..], false),
+            };
+            let port_diameter = 2.0 * style.port_radius;
+            let port_size = if is_wide {
+                let fanned = connections.len().max(2) - 1;
+                egui::vec2(
+                    port_diameter,
+                    port_diameter + fanned as f32 * WIRE_FAN_SPACING,
+                ) * zoom
+            } else {
+                egui::Vec2::splat(port_diameter) * zoom
+            };
+            let port_rect = Rect::from_center_size(port_pos, port_size);
 
             let sense = if ongoing_drag.is_some() {
                 Sense::hover()
@@ -692,48 +1715,75 @@ where
 
             // Check if the distance between the port and the mouse is the distance to connect
             let close_enough = if let Some(pointer_pos) = ui.ctx().pointer_hover_pos() {
-                port_rect.center().distance(pointer_pos) < DISTANCE_TO_CONNECT
+                port_rect.center().distance(pointer_pos) < DISTANCE_TO_CONNECT * zoom
             } else {
                 false
             };
 
-            let port_color = if close_enough {
-                Color32::WHITE
+            // While dragging a connection, check whether it could end here.
+            let compatibility = ongoing_drag.map(|(origin_node, origin_param)| {
+                match (param_id, origin_param) {
+                    // Don't allow self-loops
+                    _ if origin_node == node_id => ConnectionCompatibility::Incompatible,
+                    (AnyParameterId::Input(input), AnyParameterId::Output(output))
+                    | (AnyParameterId::Output(output), AnyParameterId::Input(input)) => {
+                        drag_compatibility(graph, output, input, cycle_guard, user_state)
+                    }
+                    // Ignore in-in or out-out connections
+                    _ => ConnectionCompatibility::Incompatible,
+                }
+            });
+            let can_connect = compatibility
+                .as_ref()
+                .is_some_and(ConnectionCompatibility::is_compatible);
+
+            let port_color = if close_enough && (ongoing_drag.is_none() || can_connect) {
+                style.port_hovered
             } else {
                 port_type.data_type_color(user_state)
             };
             ui.painter()
-                .circle(port_rect.center(), 5.0, port_color, Stroke::NONE);
+                .rect_filled(port_rect, style.port_radius * zoom, port_color);
 
             if resp.drag_started() {
-                if is_connected_input {
-                    let input = param_id.assume_input();
-                    let corresp_output = graph
-                        .connection(input)
-                        .expect("Connection data should be valid");
+                if !connections.is_empty() {
+                    // Wires are pulled out one at a time, starting with the
+                    // one closest to the pointer.
+                    let pointer_pos = resp.interact_pointer_pos().unwrap_or(port_pos);
+                    let index = (0..connections.len())
+                        .min_by(|a, b| {
+                            let dist = |i| {
+                                fanned_wire_pos(port_pos, i, connections.len(), zoom)
+                                    .distance_sq(pointer_pos)
+                            };
+                            dist(*a).total_cmp(&dist(*b))
+                        })
+                        .unwrap();
                     responses.push(NodeResponse::DisconnectEvent {
                         input: param_id.assume_input(),
-                        output: corresp_output,
+                        output: connections[index],
                     });
                 } else {
                     responses.push(NodeResponse::ConnectEventStarted(node_id, param_id));
                 }
             }
 
-            if let Some((origin_node, origin_param)) = ongoing_drag {
-                if origin_node != node_id {
-                    // Don't allow self-loops
-                    if graph.any_param_type(origin_param).unwrap() == port_type
-                        && close_enough
-                        && ui.input(|i| i.pointer.any_released())
-                    {
-                        match (param_id, origin_param) {
-                            (AnyParameterId::Input(input), AnyParameterId::Output(output))
-                            | (AnyParameterId::Output(output), AnyParameterId::Input(input)) => {
-                                responses.push(NodeResponse::ConnectEventEnded { input, output });
-                            }
-                            _ => { /* Ignore in-in or out-out connections */ }
+            if close_enough {
+                if let Some(conversion) = compatibility.as_ref().and_then(|c| c.conversion()) {
+                    show_tooltip_at_pointer(ui.ctx(), Id::new((param_id, "conversion")), |ui| {
+                        ui.label(conversion);
+                    });
+                }
+            }
+
+            if let Some((_, origin_param)) = ongoing_drag {
+                if can_connect && close_enough && ui.input(|i| i.pointer.any_released()) {
+                    match (param_id, origin_param) {
+                        (AnyParameterId::Input(input), AnyParameterId::Output(output))
+                        | (AnyParameterId::Output(output), AnyParameterId::Input(input)) => {
+                            responses.push(NodeResponse::ConnectEventEnded { input, output });
                         }
+                        _ => unreachable!("Compatible ports are always an input and an output"),
                     }
                 }
             }
@@ -745,7 +1795,7 @@ where
         for ((_, param), port_height) in self.graph[self.node_id]
             .inputs
             .iter()
-            .zip(input_port_heights.into_iter())
+            .zip(input_port_heights)
         {
             let should_draw = match self.graph[*param].kind() {
                 InputParamKind::ConnectionOnly => true,
@@ -765,7 +1815,9 @@ where
                     AnyParameterId::Input(*param),
                     self.port_locations,
                     self.ongoing_drag,
-                    self.graph.connection(*param).is_some(),
+                    self.zoom,
+                    style,
+                    self.cycle_guard,
                 );
             }
         }
@@ -774,7 +1826,7 @@ where
         for ((_, param), port_height) in self.graph[self.node_id]
             .outputs
             .iter()
-            .zip(output_port_heights.into_iter())
+            .zip(output_port_heights)
         {
             let pos_right = pos2(port_right, port_height);
             draw_port(
@@ -787,49 +1839,130 @@ where
                 AnyParameterId::Output(*param),
                 self.port_locations,
                 self.ongoing_drag,
-                false,
+                self.zoom,
+                style,
+                self.cycle_guard,
             );
         }
 
+        // Collapsed nodes draw no ports. Instead, the wires of all connected
+        // inputs and outputs meet at a single point on each side of the title
+        // bar.
+        if self.collapsed {
+            let node = &self.graph[self.node_id];
+            let connected_inputs = node
+                .input_ids()
+                .filter(|input| !self.graph.connections(*input).is_empty())
+                .map(AnyParameterId::Input);
+            // Outputs don't know their connections, so they are found in a
+            // single pass over all the connections.
+            let outputs_in_use: HashSet<OutputId> = self
+                .graph
+                .iter_connections()
+                .map(|(_, output)| output)
+                .filter(|output| self.graph[*output].node == self.node_id)
+                .collect();
+            let connected_outputs = node
+                .output_ids()
+                .filter(|output| outputs_in_use.contains(output))
+                .map(AnyParameterId::Output);
+            let port_y = outer_rect.center().y;
+            for (params, port_pos) in [
+                (
+                    connected_inputs.collect::<Vec<_>>(),
+                    pos2(port_left, port_y),
+                ),
+                (
+                    connected_outputs.collect::<Vec<_>>(),
+                    pos2(port_right, port_y),
+                ),
+            ] {
+                let Some(first) = params.first() else {
+                    continue;
+                };
+                let color = self
+                    .graph
+                    .any_param_type(*first)
+                    .unwrap()
+                    .data_type_color(user_state);
+                ui.painter()
+                    .circle_filled(port_pos, style.port_radius * self.zoom, color);
+                for param_id in params {
+                    self.port_locations.insert(param_id, port_pos);
+                }
+            }
+        }
+
         // Draw the background shape.
         // NOTE: This code is a bit more involved than it needs to be because egui
         // does not support drawing rectangles with asymmetrical round corners.
 
-        let (shape, outline) = {
-            let rounding_radius = 4.0;
+        let (shape, outline, node_rect) = {
+            let rounding_radius = style.node_rounding * self.zoom;
             let rounding = Rounding::same(rounding_radius);
 
             let titlebar_height = title_height + margin.y;
-            let titlebar_rect =
-                Rect::from_min_size(outer_rect.min, vec2(outer_rect.width(), titlebar_height));
-            let titlebar = Shape::Rect(RectShape::filled(titlebar_rect, rounding, self.graph[self.node_id]
+            let titlebar_rect = if self.collapsed {
+                outer_rect
+            } else {
+                Rect::from_min_size(outer_rect.min, vec2(outer_rect.width(), titlebar_height))
+            };
+            let titlebar = Shape::Rect(RectShape::filled(
+                titlebar_rect,
+                rounding,
+                self.graph[self.node_id]
                     .user_data
                     .titlebar_color(ui, self.node_id, self.graph, user_state)
-                    .unwrap_or_else(|| background_color.lighten(0.8))));
-
-            let body_rect = Rect::from_min_size(
-                outer_rect.min + vec2(0.0, titlebar_height - rounding_radius),
-                vec2(outer_rect.width(), outer_rect.height() - titlebar_height),
-            );
-            let body = Shape::Rect(RectShape::filled(body_rect, Rounding::ZERO, background_color));
+                    .unwrap_or(style.node_titlebar),
+            ));
 
-            let bottom_body_rect = Rect::from_min_size(
-                body_rect.min + vec2(0.0, body_rect.height() - titlebar_height * 0.5),
-                vec2(outer_rect.width(), titlebar_height),
-            );
-            let bottom_body = Shape::Rect(RectShape::filled(bottom_body_rect, rounding, background_color));
+            // Collapsed nodes are only a title bar
+            let (shapes, node_rect) = if self.collapsed {
+                (vec![titlebar], titlebar_rect)
+            } else {
+                let body_rect = Rect::from_min_size(
+                    outer_rect.min + vec2(0.0, titlebar_height - rounding_radius),
+                    vec2(outer_rect.width(), outer_rect.height() - titlebar_height),
+                );
+                let body = Shape::Rect(RectShape::filled(
+                    body_rect,
+                    Rounding::ZERO,
+                    style.node_background,
+                ));
 
-            let node_rect = titlebar_rect.union(body_rect).union(bottom_body_rect);
-            let outline = if self.selected {
-                Shape::Rect(RectShape::filled(node_rect.expand(1.0), rounding, Color32::WHITE.lighten(0.8)))
+                let bottom_body_rect = Rect::from_min_size(
+                    body_rect.min + vec2(0.0, body_rect.height() - titlebar_height * 0.5),
+                    vec2(outer_rect.width(), titlebar_height),
+                );
+                let bottom_body = Shape::Rect(RectShape::filled(
+                    bottom_body_rect,
+                    rounding,
+                    style.node_background,
+                ));
+                (
+                    vec![titlebar, body, bottom_body],
+                    titlebar_rect.union(body_rect).union(bottom_body_rect),
+                )
+            };
+            let outline_stroke = if self.selected {
+                style.node_selection
             } else {
+                style.node_outline
+            };
+            let outline = if outline_stroke.is_empty() {
                 Shape::Noop
+            } else {
+                Shape::Rect(RectShape::filled(
+                    node_rect.expand(outline_stroke.width * self.zoom),
+                    rounding,
+                    outline_stroke.color,
+                ))
             };
 
             // Take note of the node rect, so the editor can use it later to compute intersections.
             self.node_rects.insert(self.node_id, node_rect);
 
-            (Shape::Vec(vec![titlebar, body, bottom_body]), outline)
+            (Shape::Vec(shapes), outline, node_rect)
         };
 
         ui.painter().set(background_shape, shape);
@@ -844,12 +1977,53 @@ where
             user_state,
         );
 
-        if can_delete && Self::close_button(ui, outer_rect).clicked() {
+        if can_delete && Self::close_button(ui, outer_rect, self.zoom, style).clicked() {
             responses.push(NodeResponse::DeleteNodeUi(self.node_id));
         };
 
-        // Movement
-        let drag_delta = window_response.drag_delta();
+        // Resizing, by dragging the bottom-right corner. The body of the node
+        // is drawn past the outer rect, so the handle is placed at the visible
+        // corner.
+        if !self.collapsed {
+            let corner = node_rect.max;
+            let handle_size = 12.0 * self.zoom;
+            let handle_rect = Rect::from_min_max(corner - Vec2::splat(handle_size), corner);
+            let handle_response = ui
+                .interact(
+                    handle_rect,
+                    Id::new((self.node_id, "resize")),
+                    Sense::drag(),
+                )
+                .on_hover_cursor(CursorIcon::ResizeNwSe);
+            let handle_stroke = Stroke::new(1.0 * self.zoom, style.node_text.gamma_multiply(0.5));
+            for offset in [0.3, 0.65] {
+                let offset = handle_size * offset;
+                ui.painter().line_segment(
+                    [
+                        pos2(corner.x - offset, corner.y - 2.0 * self.zoom),
+                        pos2(corner.x - 2.0 * self.zoom, corner.y - offset),
+                    ],
+                    handle_stroke,
+                );
+            }
+            let resize_delta = handle_response.drag_delta() / self.zoom;
+            if resize_delta.length_sq() > 0.0 {
+                // Resized nodes start from the size they were given, and the
+                // others from the outer size their contents made them.
+                let size = resized_node_size(
+                    self.size.map(|_| min_size),
+                    outer_rect.size() / self.zoom,
+                    resize_delta,
+                );
+                responses.push(NodeResponse::ResizeNode {
+                    node: self.node_id,
+                    size,
+                });
+            }
+        }
+
+        // Movement. The drag delta is converted from screen to graph space.
+        let drag_delta = window_response.drag_delta() / self.zoom;
         if drag_delta.length_sq() > 0.0 {
             responses.push(NodeResponse::MoveNode {
                 node: self.node_id,
@@ -870,37 +2044,23 @@ where
         responses
     }
 
-    fn close_button(ui: &mut Ui, node_rect: Rect) -> Response {
+    fn close_button(ui: &mut Ui, node_rect: Rect, zoom: f32, style: &GraphStyle) -> Response {
         // Measurements
-        let margin = 8.0;
-        let size = 10.0;
-        let stroke_width = 2.0;
+        let margin = 8.0 * zoom;
+        let size = 10.0 * zoom;
+        let stroke_width = 2.0 * zoom;
         let offs = margin + size / 2.0;
 
         let position = pos2(node_rect.right() - offs, node_rect.top() + offs);
         let rect = Rect::from_center_size(position, vec2(size, size));
         let resp = ui.allocate_rect(rect, Sense::click());
 
-        let dark_mode = ui.visuals().dark_mode;
         let color = if resp.clicked() {
-            if dark_mode {
-                color_from_hex("#ffffff").unwrap()
-            } else {
-                color_from_hex("#000000").unwrap()
-            }
+            style.close_button_pressed
         } else if resp.hovered() {
-            if dark_mode {
-                color_from_hex("#dddddd").unwrap()
-            } else {
-                color_from_hex("#222222").unwrap()
-            }
+            style.close_button_hovered
         } else {
-            #[allow(clippy::collapsible_else_if)]
-            if dark_mode {
-                color_from_hex("#aaaaaa").unwrap()
-            } else {
-                color_from_hex("#555555").unwrap()
-            }
+            style.close_button
         };
         let stroke = Stroke {
             width: stroke_width,
@@ -915,3 +2075,18 @@ where
         resp
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_resize_keeps_height() {
+        let outer_size = vec2(150.0, 80.0);
+        let mut size = resized_node_size(None, outer_size, vec2(5.0, 0.0));
+        for _ in 0..10 {
+            size = resized_node_size(Some(size), outer_size, vec2(5.0, 0.0));
+        }
+        assert_eq!(size, vec2(205.0, 80.0));
+    }
+}
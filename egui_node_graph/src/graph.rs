@@ -1,4 +1,5 @@
 use super::*;
+use std::num::NonZeroU32;
 
 #[cfg(feature = "persistence")]
 use serde::{Deserialize, Serialize};
@@ -35,6 +36,11 @@ fn shown_inline_default() -> bool {
     true
 }
 
+#[cfg(feature = "persistence")]
+fn max_connections_default() -> Option<NonZeroU32> {
+    NonZeroU32::new(1)
+}
+
 /// An input parameter. Input parameters are inside a node, and represent data
 /// that this node receives. Unlike their [`OutputParam`] counterparts, input
 /// parameters also display an inline widget which allows setting its "value".
@@ -58,6 +64,11 @@ pub struct InputParam<DataType, ValueType> {
     /// When true, the node is shown inline inside the node graph.
     #[cfg_attr(feature = "persistence", serde(default = "shown_inline_default"))]
     pub shown_inline: bool,
+    /// The maximum number of incoming connections. Most inputs accept a
+    /// single connection, but "wide" inputs (e.g. the operands of a sum) can
+    /// accept several, in order. `None` means there is no limit.
+    #[cfg_attr(feature = "persistence", serde(default = "max_connections_default"))]
+    pub max_connections: Option<NonZeroU32>,
 }
 
 /// An output parameter. Output parameters are inside a node, and represent the
@@ -85,7 +96,39 @@ pub struct Graph<NodeData, DataType, ValueType> {
     pub inputs: SlotMap<InputId, InputParam<DataType, ValueType>>,
     /// The [`OutputParam`]s of the graph
     pub outputs: SlotMap<OutputId, OutputParam<DataType>>,
-    // Connects the input of a node, to the output of its predecessor that
-    // produces it
-    pub connections: SecondaryMap<InputId, OutputId>,
+    /// Connects the input of a node to the outputs of its predecessors that
+    /// produce it. Most inputs have a single connection, but inputs with
+    /// [`InputParam::max_connections`] greater than one keep an ordered list.
+    /// Inputs without connections have no entry in this map.
+    #[cfg_attr(
+        feature = "persistence",
+        serde(deserialize_with = "deserialize_connections")
+    )]
+    pub connections: SecondaryMap<InputId, Vec<OutputId>>,
+}
+
+/// Graphs saved before inputs could have multiple connections store a single
+/// output per input. This accepts both formats.
+#[cfg(feature = "persistence")]
+fn deserialize_connections<'de, D>(
+    deserializer: D,
+) -> Result<SecondaryMap<InputId, Vec<OutputId>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Connections {
+        Single(OutputId),
+        Multiple(Vec<OutputId>),
+    }
+
+    let connections = SecondaryMap::<InputId, Connections>::deserialize(deserializer)?;
+    Ok(connections
+        .into_iter()
+        .map(|(input, outputs)| match outputs {
+            Connections::Single(output) => (input, vec![output]),
+            Connections::Multiple(outputs) => (input, outputs),
+        })
+        .collect())
 }
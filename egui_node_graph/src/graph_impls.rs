@@ -1,4 +1,6 @@
 use super::*;
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
 
 impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
     pub fn new() -> Self {
@@ -40,6 +42,31 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         value: ValueType,
         kind: InputParamKind,
         shown_inline: bool,
+    ) -> InputId {
+        self.add_wide_input_param(
+            node_id,
+            name,
+            typ,
+            value,
+            kind,
+            shown_inline,
+            NonZeroU32::new(1),
+        )
+    }
+
+    /// Adds an input parameter that accepts up to `max_connections` incoming
+    /// connections, or any number of them when `None`. The connections are
+    /// kept in order, see [`Graph::connections`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_wide_input_param(
+        &mut self,
+        node_id: NodeId,
+        name: String,
+        typ: DataType,
+        value: ValueType,
+        kind: InputParamKind,
+        shown_inline: bool,
+        max_connections: Option<NonZeroU32>,
     ) -> InputId {
         let input_id = self.inputs.insert_with_key(|input_id| InputParam {
             id: input_id,
@@ -48,6 +75,7 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
             kind,
             node: node_id,
             shown_inline,
+            max_connections,
         });
         self.nodes[node_id].inputs.push((name, input_id));
         input_id
@@ -64,7 +92,10 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         let node = self[param].node;
         self[node].outputs.retain(|(_, id)| *id != param);
         self.outputs.remove(param);
-        self.connections.retain(|_, o| *o != param);
+        self.connections.retain(|_, outputs| {
+            outputs.retain(|o| *o != param);
+            !outputs.is_empty()
+        });
     }
 
     pub fn add_output_param(&mut self, node_id: NodeId, name: String, typ: DataType) -> OutputId {
@@ -87,13 +118,17 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
     pub fn remove_node(&mut self, node_id: NodeId) -> (Node<NodeData>, Vec<(InputId, OutputId)>) {
         let mut disconnect_events = vec![];
 
-        self.connections.retain(|i, o| {
-            if self.outputs[*o].node == node_id || self.inputs[i].node == node_id {
-                disconnect_events.push((i, *o));
-                false
-            } else {
-                true
-            }
+        self.connections.retain(|i, outputs| {
+            let input_removed = self.inputs[i].node == node_id;
+            outputs.retain(|o| {
+                if input_removed || self.outputs[*o].node == node_id {
+                    disconnect_events.push((i, *o));
+                    false
+                } else {
+                    true
+                }
+            });
+            !outputs.is_empty()
         });
 
         // NOTE: Collect is needed because we can't borrow the input ids while
@@ -109,24 +144,122 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         (removed_node, disconnect_events)
     }
 
+    /// Removes all the connections of `input_id`. Returns the first removed
+    /// output, which is the only one for inputs accepting a single
+    /// connection. Use [`Graph::remove_single_connection`] to remove a
+    /// specific connection from a wide input.
     pub fn remove_connection(&mut self, input_id: InputId) -> Option<OutputId> {
-        self.connections.remove(input_id)
+        self.connections
+            .remove(input_id)
+            .and_then(|outputs| outputs.into_iter().next())
+    }
+
+    /// Removes the connection between `output` and `input`, keeping any other
+    /// connections of the input. Returns false if they were not connected.
+    pub fn remove_single_connection(&mut self, output: OutputId, input: InputId) -> bool {
+        let Some(outputs) = self.connections.get_mut(input) else {
+            return false;
+        };
+        let Some(index) = outputs.iter().position(|o| *o == output) else {
+            return false;
+        };
+        outputs.remove(index);
+        if outputs.is_empty() {
+            self.connections.remove(input);
+        }
+        true
+    }
+
+    /// Moves the connection at position `from` in the connection list of
+    /// `input` to position `to`, shifting the connections in between. Out of
+    /// bounds positions are ignored.
+    pub fn reorder_connection(&mut self, input: InputId, from: usize, to: usize) {
+        if let Some(outputs) = self.connections.get_mut(input) {
+            if from < outputs.len() && to < outputs.len() {
+                let output = outputs.remove(from);
+                outputs.insert(to, output);
+            }
+        }
     }
 
     pub fn iter_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
         self.nodes.iter().map(|(id, _)| id)
     }
 
+    /// Connects `output` to `input`. When the input already has its maximum
+    /// number of connections, the oldest ones are removed to make room. For
+    /// inputs accepting a single connection, this replaces the connection.
+    /// Does nothing if `output` is already connected to `input`.
     pub fn add_connection(&mut self, output: OutputId, input: InputId) {
-        self.connections.insert(input, output);
+        if self.connections(input).contains(&output) {
+            return;
+        }
+        let max_connections = self[input].max_connections;
+        let outputs = match self.connections.get_mut(input) {
+            Some(outputs) => {
+                outputs.push(output);
+                outputs
+            }
+            None => {
+                self.connections.insert(input, vec![output]);
+                &mut self.connections[input]
+            }
+        };
+        if let Some(max_connections) = max_connections {
+            let excess = outputs.len().saturating_sub(max_connections.get() as usize);
+            outputs.drain(..excess);
+        }
     }
 
+    /// Iterates all the connections of the graph as input-output pairs. Wide
+    /// inputs produce one pair per connection, in order.
     pub fn iter_connections(&self) -> impl Iterator<Item = (InputId, OutputId)> + '_ {
-        self.connections.iter().map(|(o, i)| (o, *i))
+        self.connections
+            .iter()
+            .flat_map(|(input, outputs)| outputs.iter().map(move |output| (input, *output)))
     }
 
+    /// Returns the output connected to `input`. For wide inputs, this is the
+    /// first of its connections. See [`Graph::connections`].
     pub fn connection(&self, input: InputId) -> Option<OutputId> {
-        self.connections.get(input).copied()
+        self.connections
+            .get(input)
+            .and_then(|outputs| outputs.first().copied())
+    }
+
+    /// Returns all the outputs connected to `input`, in order.
+    pub fn connections(&self, input: InputId) -> &[OutputId] {
+        self.connections
+            .get(input)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns true when `input` cannot take more connections without
+    /// dropping an existing one. Inputs accepting a single connection are
+    /// never considered full, since connecting to them replaces the previous
+    /// connection.
+    pub fn is_input_full(&self, input: InputId) -> bool {
+        match self[input].max_connections {
+            Some(max) if max.get() > 1 => self.connections(input).len() >= max.get() as usize,
+            _ => false,
+        }
+    }
+
+    /// Checks whether `output` can be connected to `input`, as decided by
+    /// [`DataTypeTrait::connection_compatibility`].
+    pub fn connection_compatibility<UserState>(
+        &self,
+        output: OutputId,
+        input: InputId,
+        user_state: &mut UserState,
+    ) -> ConnectionCompatibility
+    where
+        DataType: DataTypeTrait<UserState>,
+    {
+        self[output]
+            .typ
+            .connection_compatibility(&self[input].typ, user_state)
     }
 
     pub fn any_param_type(&self, param: AnyParameterId) -> Result<&DataType, EguiGraphError> {
@@ -160,18 +293,192 @@ impl<NodeData, DataType, ValueType> Default for Graph<NodeData, DataType, ValueT
     }
 }
 
+/// Graph algorithms over the node connectivity. An edge goes from a node to
+/// every node that has an input connected to one of its outputs.
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Returns the nodes directly downstream of every node in the graph.
+    fn node_successors(&self) -> SecondaryMap<NodeId, Vec<NodeId>> {
+        let mut successors: SecondaryMap<NodeId, Vec<NodeId>> =
+            self.nodes.keys().map(|node| (node, Vec::new())).collect();
+        for (input, output) in self.iter_connections() {
+            successors[self.outputs[output].node].push(self.inputs[input].node);
+        }
+        successors
+    }
+
+    /// Returns the nodes directly upstream of every node in the graph.
+    fn node_predecessors(&self) -> SecondaryMap<NodeId, Vec<NodeId>> {
+        let mut predecessors: SecondaryMap<NodeId, Vec<NodeId>> =
+            self.nodes.keys().map(|node| (node, Vec::new())).collect();
+        for (input, output) in self.iter_connections() {
+            predecessors[self.inputs[input].node].push(self.outputs[output].node);
+        }
+        predecessors
+    }
+
+    /// Returns the nodes reached from `start` by following `edges`, including
+    /// `start` itself.
+    fn reach(start: NodeId, edges: &SecondaryMap<NodeId, Vec<NodeId>>) -> SecondaryMap<NodeId, ()> {
+        let mut visited = SecondaryMap::new();
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            if visited.insert(node, ()).is_none() {
+                stack.extend(edges[node].iter().copied());
+            }
+        }
+        visited
+    }
+
+    /// Returns the nodes reachable from `from` by following connections
+    /// downstream, including `from` itself. Connecting an output of any of
+    /// these nodes to an input of `from` would create a cycle.
+    pub fn downstream_nodes(&self, from: NodeId) -> SecondaryMap<NodeId, ()> {
+        Self::reach(from, &self.node_successors())
+    }
+
+    /// Returns the nodes `to` is reachable from by following connections
+    /// downstream, including `to` itself. Connecting an output of `to` to an
+    /// input of any of these nodes would create a cycle.
+    pub fn upstream_nodes(&self, to: NodeId) -> SecondaryMap<NodeId, ()> {
+        Self::reach(to, &self.node_predecessors())
+    }
+
+    /// Returns all the nodes in an order where every node comes after all the
+    /// nodes it depends on. Fails with [`EguiGraphError::Cycle`] when the graph
+    /// has cycles, listing the nodes that could not be ordered.
+    pub fn topological_order(&self) -> Result<Vec<NodeId>, EguiGraphError> {
+        let successors = self.node_successors();
+        let mut in_degree: SecondaryMap<NodeId, usize> =
+            self.nodes.keys().map(|node| (node, 0)).collect();
+        for next in successors.values().flatten() {
+            in_degree[*next] += 1;
+        }
+
+        let mut ready: VecDeque<NodeId> = self
+            .nodes
+            .keys()
+            .filter(|node| in_degree[*node] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node) = ready.pop_front() {
+            order.push(node);
+            for next in &successors[node] {
+                in_degree[*next] -= 1;
+                if in_degree[*next] == 0 {
+                    ready.push_back(*next);
+                }
+            }
+        }
+
+        if order.len() == self.nodes.len() {
+            Ok(order)
+        } else {
+            Err(EguiGraphError::Cycle(
+                self.nodes
+                    .keys()
+                    .filter(|node| in_degree[*node] > 0)
+                    .collect(),
+            ))
+        }
+    }
+
+    /// Returns true if `to` can be reached from `from` by following
+    /// connections downstream. A node is always reachable from itself.
+    pub fn is_reachable(&self, from: NodeId, to: NodeId) -> bool {
+        let successors = self.node_successors();
+        let mut visited = SecondaryMap::new();
+        let mut stack = vec![from];
+        while let Some(node) = stack.pop() {
+            if node == to {
+                return true;
+            }
+            if visited.insert(node, ()).is_none() {
+                stack.extend(successors[node].iter().copied());
+            }
+        }
+        false
+    }
+
+    /// Returns true if connecting `output` to `input` would introduce a cycle
+    /// in the graph, including a node connected to itself.
+    pub fn would_create_cycle(&self, output: OutputId, input: InputId) -> bool {
+        self.is_reachable(self.inputs[input].node, self.outputs[output].node)
+    }
+
+    /// Returns the strongly connected components of the graph, in reverse
+    /// topological order. Every node belongs to exactly one component. The
+    /// graph has no cycles when all components have a single node and no node
+    /// is connected to itself.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        // Tarjan's algorithm, with an explicit stack instead of recursion.
+        let successors = self.node_successors();
+        let mut index: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+        let mut low_link: SecondaryMap<NodeId, usize> = SecondaryMap::new();
+        let mut on_stack: SecondaryMap<NodeId, ()> = SecondaryMap::new();
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+
+        for root in self.nodes.keys() {
+            if index.contains_key(root) {
+                continue;
+            }
+
+            // Each frame holds a node and the position of its next successor
+            let mut call_stack = vec![(root, 0)];
+            index.insert(root, index.len());
+            low_link.insert(root, index[root]);
+            on_stack.insert(root, ());
+            stack.push(root);
+
+            while let Some((node, next_successor)) = call_stack.last_mut() {
+                let node = *node;
+                if let Some(&next) = successors[node].get(*next_successor) {
+                    *next_successor += 1;
+                    if !index.contains_key(next) {
+                        index.insert(next, index.len());
+                        low_link.insert(next, index[next]);
+                        on_stack.insert(next, ());
+                        stack.push(next);
+                        call_stack.push((next, 0));
+                    } else if on_stack.contains_key(next) {
+                        low_link[node] = low_link[node].min(index[next]);
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low_link[parent] = low_link[parent].min(low_link[node]);
+                }
+                if low_link[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack.remove(member);
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+}
+
 impl<NodeData> Node<NodeData> {
     pub fn inputs<'a, DataType, DataValue>(
         &'a self,
         graph: &'a Graph<NodeData, DataType, DataValue>,
-    ) -> impl Iterator<Item = &InputParam<DataType, DataValue>> + 'a {
+    ) -> impl Iterator<Item = &'a InputParam<DataType, DataValue>> + 'a {
         self.input_ids().map(|id| graph.get_input(id))
     }
 
     pub fn outputs<'a, DataType, DataValue>(
         &'a self,
         graph: &'a Graph<NodeData, DataType, DataValue>,
-    ) -> impl Iterator<Item = &OutputParam<DataType>> + 'a {
+    ) -> impl Iterator<Item = &'a OutputParam<DataType>> + 'a {
         self.output_ids().map(|id| graph.get_output(id))
     }
 
@@ -209,7 +516,146 @@ impl<DataType, ValueType> InputParam<DataType, ValueType> {
         self.kind
     }
 
+    pub fn max_connections(&self) -> Option<NonZeroU32> {
+        self.max_connections
+    }
+
     pub fn node(&self) -> NodeId {
         self.node
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = Graph<(), (), ()>;
+
+    fn add_node(graph: &mut TestGraph, max_connections: Option<NonZeroU32>) -> NodeId {
+        graph.add_node(String::new(), (), |graph, node_id| {
+            graph.add_wide_input_param(
+                node_id,
+                "in".into(),
+                (),
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+                max_connections,
+            );
+            graph.add_output_param(node_id, "out".into(), ());
+        })
+    }
+
+    fn input(graph: &TestGraph, node: NodeId) -> InputId {
+        graph[node].get_input("in").unwrap()
+    }
+
+    fn output(graph: &TestGraph, node: NodeId) -> OutputId {
+        graph[node].get_output("out").unwrap()
+    }
+
+    #[test]
+    pub fn test_single_connection_is_replaced() {
+        let mut graph = TestGraph::new();
+        let [a, b, c] = [(); 3].map(|_| add_node(&mut graph, NonZeroU32::new(1)));
+
+        graph.add_connection(output(&graph, a), input(&graph, c));
+        graph.add_connection(output(&graph, b), input(&graph, c));
+        assert_eq!(graph.connections(input(&graph, c)), &[output(&graph, b)]);
+        assert!(!graph.is_input_full(input(&graph, c)));
+    }
+
+    #[test]
+    pub fn test_wide_input_connections() {
+        let mut graph = TestGraph::new();
+        let [a, b, c] = [(); 3].map(|_| add_node(&mut graph, None));
+        let sum = add_node(&mut graph, NonZeroU32::new(3));
+        let sum_input = input(&graph, sum);
+
+        for node in [a, b, c] {
+            graph.add_connection(output(&graph, node), sum_input);
+        }
+        let [out_a, out_b, out_c] = [a, b, c].map(|node| output(&graph, node));
+        assert_eq!(graph.connections(sum_input), &[out_a, out_b, out_c]);
+        assert_eq!(graph.connection(sum_input), Some(out_a));
+        assert!(graph.is_input_full(sum_input));
+        assert_eq!(graph.iter_connections().count(), 3);
+
+        graph.reorder_connection(sum_input, 2, 0);
+        assert_eq!(graph.connections(sum_input), &[out_c, out_a, out_b]);
+
+        assert!(graph.remove_single_connection(out_a, sum_input));
+        assert!(!graph.remove_single_connection(out_a, sum_input));
+        assert_eq!(graph.connections(sum_input), &[out_c, out_b]);
+
+        let (_, disconnected) = graph.remove_node(c);
+        assert_eq!(disconnected, vec![(sum_input, out_c)]);
+        assert_eq!(graph.connections(sum_input), &[out_b]);
+
+        graph.remove_output_param(out_b);
+        assert!(graph.connections(sum_input).is_empty());
+        assert!(!graph.connections.contains_key(sum_input));
+    }
+
+    #[test]
+    pub fn test_duplicate_connection_is_ignored() {
+        let mut graph = TestGraph::new();
+        let a = add_node(&mut graph, None);
+        let sum = add_node(&mut graph, None);
+        let (out_a, sum_input) = (output(&graph, a), input(&graph, sum));
+
+        graph.add_connection(out_a, sum_input);
+        graph.add_connection(out_a, sum_input);
+        assert_eq!(graph.connections(sum_input), &[out_a]);
+        assert!(graph.remove_single_connection(out_a, sum_input));
+        assert!(graph.connections(sum_input).is_empty());
+    }
+
+    #[test]
+    pub fn test_cycle_detection() {
+        let mut graph = TestGraph::new();
+        let [a, b, c] = [(); 3].map(|_| add_node(&mut graph, None));
+        let d = add_node(&mut graph, None);
+
+        graph.add_connection(output(&graph, b), input(&graph, c));
+        graph.add_connection(output(&graph, a), input(&graph, b));
+        graph.add_connection(output(&graph, a), input(&graph, d));
+        let order = graph.topological_order().unwrap();
+        let position = |node| order.iter().position(|n| *n == node).unwrap();
+        assert!(position(a) < position(b));
+        assert!(position(b) < position(c));
+        assert!(position(a) < position(d));
+        assert_eq!(graph.strongly_connected_components().len(), 4);
+
+        assert!(graph.would_create_cycle(output(&graph, c), input(&graph, a)));
+        assert!(graph.would_create_cycle(output(&graph, a), input(&graph, a)));
+        assert!(!graph.would_create_cycle(output(&graph, d), input(&graph, c)));
+        let sorted_keys = |nodes: SecondaryMap<NodeId, ()>| {
+            let mut keys: Vec<NodeId> = nodes.keys().collect();
+            keys.sort();
+            keys
+        };
+        let mut expected = vec![a, b, c, d];
+        expected.sort();
+        assert_eq!(sorted_keys(graph.downstream_nodes(a)), expected);
+        let mut expected = vec![a, b, c];
+        expected.sort();
+        assert_eq!(sorted_keys(graph.upstream_nodes(c)), expected);
+
+        graph.add_connection(output(&graph, c), input(&graph, a));
+        match graph.topological_order() {
+            Err(EguiGraphError::Cycle(nodes)) => assert_eq!(nodes.len(), 4),
+            other => panic!("Expected a cycle, got {other:?}"),
+        }
+
+        let mut components = graph.strongly_connected_components();
+        components.sort_by_key(|component| component.len());
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0], vec![d]);
+        let mut cycle = components[1].clone();
+        cycle.sort();
+        let mut expected = vec![a, b, c];
+        expected.sort();
+        assert_eq!(cycle, expected);
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+use egui::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Grid lines closer than this on screen are not drawn.
+const MIN_GRID_LINE_SPACING: f32 = 8.0;
+
+/// The grid drawn in the background of the editor, which nodes can be
+/// snapped to. See [`GraphEditorState::grid`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct Grid {
+    /// Distance between two grid lines, in graph space.
+    pub spacing: f32,
+    /// Every `major_every` lines, a more visible major line is drawn.
+    pub major_every: u32,
+    /// Draws the grid in the background of the editor.
+    pub show: bool,
+    /// Snaps nodes to the grid when they are dragged, created or pasted.
+    /// Holding alt disables snapping.
+    pub snap: bool,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            spacing: 20.0,
+            major_every: 5,
+            show: false,
+            snap: false,
+        }
+    }
+}
+
+impl Grid {
+    /// Returns the grid point closest to `pos`, in graph space.
+    pub fn snap_pos(&self, pos: Pos2) -> Pos2 {
+        if self.spacing <= 0.0 {
+            return pos;
+        }
+        ((pos.to_vec2() / self.spacing).round() * self.spacing).to_pos2()
+    }
+
+    /// Draws the grid lines visible in `editor_rect`.
+    pub(crate) fn draw(&self, ui: &Ui, editor_rect: Rect, pan_zoom: &PanZoom) {
+        let screen_spacing = self.spacing * pan_zoom.zoom;
+        let major_every = self.major_every.max(1) as i64;
+        if screen_spacing * (major_every as f32) < MIN_GRID_LINE_SPACING {
+            return;
+        }
+        let draw_minor = screen_spacing >= MIN_GRID_LINE_SPACING;
+        let minor_stroke = Stroke::new(1.0, ui.visuals().text_color().gamma_multiply(0.04));
+        let major_stroke = Stroke::new(1.0, ui.visuals().text_color().gamma_multiply(0.1));
+
+        let min = pan_zoom.screen_to_graph(editor_rect.min, editor_rect.min);
+        let max = pan_zoom.screen_to_graph(editor_rect.min, editor_rect.max);
+        let painter = ui.painter().with_clip_rect(editor_rect);
+        let lines = |from: f32, to: f32| {
+            ((from / self.spacing).floor() as i64)..=((to / self.spacing).ceil() as i64)
+        };
+        for index in lines(min.x, max.x) {
+            let major = index % major_every == 0;
+            if major || draw_minor {
+                let x = pan_zoom
+                    .graph_to_screen(editor_rect.min, pos2(index as f32 * self.spacing, 0.0))
+                    .x;
+                painter.vline(
+                    x,
+                    editor_rect.y_range(),
+                    if major { major_stroke } else { minor_stroke },
+                );
+            }
+        }
+        for index in lines(min.y, max.y) {
+            let major = index % major_every == 0;
+            if major || draw_minor {
+                let y = pan_zoom
+                    .graph_to_screen(editor_rect.min, pos2(0.0, index as f32 * self.spacing))
+                    .y;
+                painter.hline(
+                    editor_rect.x_range(),
+                    y,
+                    if major { major_stroke } else { minor_stroke },
+                );
+            }
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Moves `nodes` to the closest points of the grid.
+    pub fn snap_to_grid(&mut self, nodes: &[NodeId]) {
+        for node_id in nodes {
+            if let Some(pos) = self.node_positions.get_mut(*node_id) {
+                *pos = self.grid.snap_pos(*pos);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_snap_pos() {
+        let grid = Grid {
+            spacing: 20.0,
+            ..Default::default()
+        };
+        assert_eq!(grid.snap_pos(pos2(9.0, 11.0)), pos2(0.0, 20.0));
+        assert_eq!(grid.snap_pos(pos2(-31.0, 40.0)), pos2(-40.0, 40.0));
+    }
+}
@@ -0,0 +1,676 @@
+use super::*;
+use crate::scale::Scale;
+use egui::*;
+use std::collections::HashSet;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Space between the frame of an expanded group and its nodes, at zoom 1.0.
+pub(crate) const GROUP_FRAME_MARGIN: f32 = 10.0;
+/// Height of the title bar of an expanded group frame, at zoom 1.0.
+const GROUP_HEADER_HEIGHT: f32 = 24.0;
+
+/// A set of nodes that can be collapsed into a single node, or entered as a
+/// nested view of the graph. Groups only exist in the editor: the nodes and
+/// connections of the [`Graph`] are the same whether they are grouped or not.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct NodeGroup {
+    pub label: String,
+    /// The nodes directly inside this group. Nodes inside nested groups are
+    /// listed by those groups instead.
+    pub nodes: Vec<NodeId>,
+    /// The group containing this one, if any.
+    pub parent: Option<GroupId>,
+    /// The position of the collapsed group node, in graph space.
+    pub position: egui::Pos2,
+    /// Collapsed groups are drawn as a single node, with a port for every
+    /// connection crossing the group boundary.
+    pub collapsed: bool,
+}
+
+/// How a node is displayed in the current view of the editor. See
+/// [`GraphEditorState::node_view`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeView {
+    /// The node is drawn normally.
+    Visible,
+    /// The node is inside a collapsed group, which is drawn in its place.
+    Collapsed(GroupId),
+    /// The node is outside the group the user has entered.
+    Hidden,
+}
+
+/// The ports of a collapsed group. These are the ports of the nodes inside
+/// the group that are connected to nodes outside of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupPorts {
+    pub inputs: Vec<InputId>,
+    pub outputs: Vec<OutputId>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Returns the group directly containing each grouped node.
+    pub fn node_groups(&self) -> SecondaryMap<NodeId, GroupId> {
+        let mut node_groups = SecondaryMap::new();
+        for (group_id, group) in &self.groups {
+            for node_id in &group.nodes {
+                if self.graph.nodes.contains_key(*node_id) {
+                    node_groups.insert(*node_id, group_id);
+                }
+            }
+        }
+        node_groups
+    }
+
+    /// Returns `group` followed by all the groups containing it, from the
+    /// innermost to the outermost.
+    pub fn group_ancestors(&self, group: GroupId) -> Vec<GroupId> {
+        let mut ancestors = Vec::new();
+        let mut current = Some(group);
+        // The containment check guards against malformed parent chains.
+        while let Some(group_id) =
+            current.filter(|id| self.groups.contains_key(*id) && !ancestors.contains(id))
+        {
+            ancestors.push(group_id);
+            current = self.groups[group_id].parent;
+        }
+        ancestors
+    }
+
+    /// Returns how `node` is displayed, given the entered group and the
+    /// collapsed state of the groups containing it.
+    pub fn node_view(&self, node: NodeId) -> NodeView {
+        self.grouped_view(self.node_groups().get(node).copied())
+    }
+
+    /// Returns how the contents of `group` are displayed. `None` stands for
+    /// the nodes that are not in any group.
+    pub(crate) fn grouped_view(&self, group: Option<GroupId>) -> NodeView {
+        let chain = group
+            .map(|group| self.group_ancestors(group))
+            .unwrap_or_default();
+        let inner = match self.open_group {
+            Some(open_group) => match chain.iter().position(|id| *id == open_group) {
+                Some(index) => &chain[..index],
+                None => return NodeView::Hidden,
+            },
+            None => &chain[..],
+        };
+        // The outermost collapsed group hides all the groups inside it.
+        match inner.iter().rev().find(|id| self.groups[**id].collapsed) {
+            Some(group_id) => NodeView::Collapsed(*group_id),
+            None => NodeView::Visible,
+        }
+    }
+
+    /// Returns all the nodes inside `group`, including the ones inside nested
+    /// groups, in drawing order.
+    pub fn group_descendants(&self, group: GroupId) -> Vec<NodeId> {
+        let node_groups = self.node_groups();
+        self.node_order
+            .iter()
+            .copied()
+            .filter(|node_id| {
+                node_groups
+                    .get(*node_id)
+                    .is_some_and(|inner| self.group_ancestors(*inner).contains(&group))
+            })
+            .collect()
+    }
+
+    /// Returns the ports shown by `group` when it is collapsed.
+    pub fn group_ports(&self, group: GroupId) -> GroupPorts {
+        let descendants = self.group_descendants(group);
+        let nodes: HashSet<NodeId> = descendants.iter().copied().collect();
+        let is_outside = |node_id: NodeId| !nodes.contains(&node_id);
+
+        let mut outputs_leaving = HashSet::new();
+        for (input, output) in self.graph.iter_connections() {
+            if is_outside(self.graph[input].node) && !is_outside(self.graph[output].node) {
+                outputs_leaving.insert(output);
+            }
+        }
+
+        let mut ports = GroupPorts::default();
+        for node_id in descendants {
+            let node = &self.graph[node_id];
+            ports.inputs.extend(node.input_ids().filter(|input| {
+                self.graph
+                    .connections(*input)
+                    .iter()
+                    .any(|output| is_outside(self.graph[*output].node))
+            }));
+            ports.outputs.extend(
+                node.output_ids()
+                    .filter(|output| outputs_leaving.contains(output)),
+            );
+        }
+        ports
+    }
+
+    /// Puts `nodes` in a new, collapsed group inside the entered group. The
+    /// nodes are taken out of the groups they were in.
+    pub fn create_group(&mut self, nodes: &[NodeId], label: impl Into<String>) -> GroupId {
+        for group in self.groups.values_mut() {
+            group.nodes.retain(|node_id| !nodes.contains(node_id));
+        }
+        let position = nodes
+            .iter()
+            .filter_map(|node_id| self.node_positions.get(*node_id).copied())
+            .reduce(egui::Pos2::min)
+            .unwrap_or_default();
+        let group = self.groups.insert(NodeGroup {
+            label: label.into(),
+            nodes: nodes.to_vec(),
+            parent: self.open_group,
+            position,
+            collapsed: true,
+        });
+        self.prune_groups();
+        group
+    }
+
+    /// Adds `nodes` to the entered group, if any, so that new nodes appear in
+    /// the view they were created from.
+    pub(crate) fn add_to_open_group(&mut self, nodes: &[NodeId]) {
+        if let Some(group) = self.open_group.and_then(|id| self.groups.get_mut(id)) {
+            group.nodes.extend_from_slice(nodes);
+        }
+    }
+
+    /// Removes `group`, moving its nodes and nested groups to the group
+    /// containing it.
+    pub fn ungroup(&mut self, group: GroupId) {
+        let Some(removed) = self.groups.remove(group) else {
+            return;
+        };
+        if let Some(parent) = removed.parent.and_then(|id| self.groups.get_mut(id)) {
+            parent.nodes.extend(removed.nodes);
+        }
+        for other in self.groups.values_mut() {
+            if other.parent == Some(group) {
+                other.parent = removed.parent;
+            }
+        }
+        if self.open_group == Some(group) {
+            self.open_group = removed.parent;
+        }
+    }
+
+    /// Removes deleted nodes from their groups, then removes the groups left
+    /// without any nodes.
+    pub fn prune_groups(&mut self) {
+        let graph = &self.graph;
+        for group in self.groups.values_mut() {
+            group
+                .nodes
+                .retain(|node_id| graph.nodes.contains_key(*node_id));
+        }
+        loop {
+            let empty: Vec<GroupId> = self
+                .groups
+                .iter()
+                .filter(|(group_id, group)| {
+                    group.nodes.is_empty()
+                        && !self
+                            .groups
+                            .values()
+                            .any(|other| other.parent == Some(*group_id))
+                })
+                .map(|(group_id, _)| group_id)
+                .collect();
+            if empty.is_empty() {
+                break;
+            }
+            for group_id in empty {
+                self.ungroup(group_id);
+            }
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: DataTypeTrait<UserState> + Clone,
+    ValueType: Clone,
+{
+    /// Draws the collapsed groups as nodes, the frames around the expanded
+    /// groups and the breadcrumb to leave the entered group, then applies the
+    /// changes made through them. The frames are painted into
+    /// `group_frames_shape`, added before the nodes so it is painted behind
+    /// them.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn draw_groups(
+        &mut self,
+        ui: &mut Ui,
+        editor_rect: Rect,
+        style: &GraphStyle,
+        node_views: &SecondaryMap<NodeId, NodeView>,
+        node_groups: &SecondaryMap<NodeId, GroupId>,
+        node_rects: &NodeRects,
+        port_locations: &mut PortLocations,
+        group_frames_shape: egui::layers::ShapeIdx,
+        user_state: &mut UserState,
+    ) {
+        let zoom = self.pan_zoom.zoom;
+        let is_visible = |node_id: NodeId| node_views.get(node_id) == Some(&NodeView::Visible);
+        let mut group_actions = Vec::new();
+
+        let mut collapsed_groups: Vec<GroupId> = Vec::new();
+        for view in self.node_order.iter().filter_map(|id| node_views.get(*id)) {
+            if let NodeView::Collapsed(group) = view {
+                if !collapsed_groups.contains(group) {
+                    collapsed_groups.push(*group);
+                }
+            }
+        }
+        for group_id in collapsed_groups {
+            let group = &self.groups[group_id];
+            group_actions.extend(draw_group_node(
+                ui,
+                &self.graph,
+                group_id,
+                group,
+                &self.group_ports(group_id),
+                self.pan_zoom
+                    .graph_to_screen(editor_rect.min, group.position),
+                zoom,
+                style,
+                port_locations,
+                user_state,
+            ));
+        }
+
+        // Expanded groups are framed around their visible nodes. Inner groups
+        // are measured first, so the frames of outer groups can contain them.
+        let view_depth = |group: GroupId| {
+            self.group_ancestors(group)
+                .into_iter()
+                .take_while(|id| Some(*id) != self.open_group)
+                .count()
+        };
+        let mut expanded_groups: Vec<GroupId> = Vec::new();
+        for node_id in self.node_order.iter().copied().filter(|id| is_visible(*id)) {
+            if let Some(group) = node_groups.get(node_id) {
+                for group in self.group_ancestors(*group) {
+                    if Some(group) == self.open_group {
+                        break;
+                    }
+                    if !expanded_groups.contains(&group) {
+                        expanded_groups.push(group);
+                    }
+                }
+            }
+        }
+        expanded_groups.sort_by_key(|group| std::cmp::Reverse(view_depth(*group)));
+
+        let mut group_frames: Vec<(GroupId, Rect)> = Vec::new();
+        for group_id in expanded_groups {
+            let group = &self.groups[group_id];
+            let contents = group
+                .nodes
+                .iter()
+                .filter_map(|node_id| node_rects.get(node_id).copied())
+                .chain(
+                    group_frames
+                        .iter()
+                        .filter(|(inner, _)| self.groups[*inner].parent == Some(group_id))
+                        .map(|(_, rect)| *rect),
+                )
+                .fold(Rect::NOTHING, Rect::union);
+            let mut frame = contents.expand(GROUP_FRAME_MARGIN * zoom);
+            frame.min.y -= GROUP_HEADER_HEIGHT * zoom;
+            group_frames.push((group_id, frame));
+        }
+
+        let mut frame_shapes = Vec::new();
+        // Outer frames are painted first, so inner frames stay visible.
+        for (group_id, frame) in group_frames.iter().rev() {
+            frame_shapes.push(Shape::rect_filled(
+                *frame,
+                4.0 * zoom,
+                ui.visuals().text_color().gamma_multiply(0.06),
+            ));
+            frame_shapes.push(Shape::rect_stroke(
+                *frame,
+                4.0 * zoom,
+                Stroke::new(1.0 * zoom, ui.visuals().weak_text_color()),
+            ));
+            group_actions.extend(draw_group_frame_header(
+                ui,
+                *group_id,
+                &self.groups[*group_id],
+                *frame,
+                zoom,
+            ));
+        }
+        ui.painter()
+            .set(group_frames_shape, Shape::Vec(frame_shapes));
+
+        // Breadcrumb to leave the entered group
+        if let Some(open_group) = self.open_group {
+            let mut breadcrumb_ui = ui.child_ui(
+                editor_rect.shrink(8.0),
+                Layout::left_to_right(Align::Center),
+            );
+            if breadcrumb_ui.button("Graph").clicked() {
+                group_actions.push(GroupAction::Open(None));
+            }
+            for group_id in self.group_ancestors(open_group).into_iter().rev() {
+                breadcrumb_ui.label("›");
+                if breadcrumb_ui.button(&self.groups[group_id].label).clicked() {
+                    group_actions.push(GroupAction::Open(Some(group_id)));
+                }
+            }
+        }
+
+        for action in group_actions {
+            match action {
+                GroupAction::Move(group, drag_delta) => {
+                    self.record_undo_step(Some(UndoGroup::MoveNodes));
+                    self.groups[group].position += drag_delta;
+                }
+                GroupAction::SetCollapsed(group, collapsed) => {
+                    self.record_undo_step(None);
+                    self.groups[group].collapsed = collapsed;
+                }
+                GroupAction::Ungroup(group) => {
+                    self.record_undo_step(None);
+                    self.ungroup(group);
+                }
+                GroupAction::Open(group) => {
+                    self.open_group = group;
+                    self.selected_nodes.clear();
+                }
+            }
+        }
+    }
+}
+
+/// Changes to node groups requested from their widgets. These are applied
+/// after all the groups are drawn.
+enum GroupAction {
+    Move(GroupId, Vec2),
+    SetCollapsed(GroupId, bool),
+    Ungroup(GroupId),
+    /// Enters the given group, or goes back to the whole graph.
+    Open(Option<GroupId>),
+}
+
+/// Draws a collapsed group as a node, at `screen_pos`. The group shows a port
+/// for every connection crossing its boundary, and the wires of those
+/// connections are attached to it by overriding the locations of the ports
+/// inside the group.
+#[allow(clippy::too_many_arguments)]
+fn draw_group_node<NodeData, DataType, ValueType, UserState>(
+    ui: &mut Ui,
+    graph: &Graph<NodeData, DataType, ValueType>,
+    group_id: GroupId,
+    group: &NodeGroup,
+    ports: &GroupPorts,
+    screen_pos: Pos2,
+    zoom: f32,
+    style: &GraphStyle,
+    port_locations: &mut PortLocations,
+    user_state: &mut UserState,
+) -> Option<GroupAction>
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    // Groups are sized like nodes that were never resized: they shrink to fit
+    // their contents, within the default constraints of nodes.
+    let constraints = NodeSizeConstraints::default();
+    let mut child_ui = ui.child_ui_with_id_source(
+        Rect::from_min_size(screen_pos, constraints.fit.max(constraints.min) * zoom),
+        Layout::default(),
+        group_id,
+    );
+    child_ui.style_mut().scale(zoom);
+    let ui = &mut child_ui;
+
+    let margin = style.node_margin * zoom;
+    let background_shape = ui.painter().add(Shape::Noop);
+
+    // As with nodes, the interaction rect is taken from the previous frame so
+    // the buttons drawn on top of it take precedence.
+    let id = ui.id().with("group_node");
+    let interaction_rect = ui
+        .ctx()
+        .memory_mut(|mem| mem.data.get_temp::<OuterRectMemory>(id))
+        .map_or(Rect::NOTHING, |stored| stored.0);
+    let window_response = ui.interact(interaction_rect, id, Sense::click_and_drag());
+
+    let mut action = None;
+    let mut title_height = 0.0;
+    let mut input_heights = Vec::new();
+    let mut output_heights = Vec::new();
+    let port_label = |node_id: NodeId, name: &str| format!("{}: {}", graph[node_id].label, name);
+
+    let mut content_ui = ui.child_ui(ui.max_rect().shrink2(margin), Layout::default());
+    content_ui.set_min_size((constraints.min * zoom - 2.0 * margin).max(Vec2::ZERO));
+    content_ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(&group.label)
+                    .text_style(style.title_text_style.clone())
+                    .color(style.node_text),
+            );
+            if ui.small_button("Expand").clicked() {
+                action = Some(GroupAction::SetCollapsed(group_id, false));
+            }
+            if ui.small_button("Enter").clicked() {
+                action = Some(GroupAction::Open(Some(group_id)));
+            }
+            if ui.small_button("Ungroup").clicked() {
+                action = Some(GroupAction::Ungroup(group_id));
+            }
+        });
+        ui.add_space(margin.y);
+        title_height = ui.min_size().y;
+
+        for input in &ports.inputs {
+            let node_id = graph[*input].node;
+            let (name, _) = graph[node_id]
+                .inputs
+                .iter()
+                .find(|(_, id)| id == input)
+                .expect("Inputs should belong to their node");
+            let height_before = ui.min_rect().bottom();
+            ui.label(port_label(node_id, name));
+            input_heights.push((height_before + ui.min_rect().bottom()) / 2.0);
+        }
+        for output in &ports.outputs {
+            let node_id = graph[*output].node;
+            let (name, _) = graph[node_id]
+                .outputs
+                .iter()
+                .find(|(_, id)| id == output)
+                .expect("Outputs should belong to their node");
+            let height_before = ui.min_rect().bottom();
+            ui.label(port_label(node_id, name));
+            output_heights.push((height_before + ui.min_rect().bottom()) / 2.0);
+        }
+    });
+
+    let outer_rect = content_ui.min_rect().expand2(margin);
+    ui.ctx()
+        .memory_mut(|mem| mem.data.insert_temp(id, OuterRectMemory(outer_rect)));
+
+    let rounding = style.node_rounding * zoom;
+    let titlebar_rect = Rect::from_min_size(
+        outer_rect.min,
+        vec2(outer_rect.width(), title_height + margin.y),
+    );
+    ui.painter().set(
+        background_shape,
+        Shape::Vec(vec![
+            Shape::rect_filled(outer_rect, rounding, style.node_background),
+            Shape::rect_filled(
+                titlebar_rect,
+                Rounding {
+                    nw: rounding,
+                    ne: rounding,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                style.node_titlebar,
+            ),
+            // A double outline tells groups apart from regular nodes
+            Shape::rect_stroke(
+                outer_rect.expand(3.0 * zoom),
+                rounding,
+                Stroke::new(1.0 * zoom, style.node_titlebar),
+            ),
+        ]),
+    );
+
+    let port_ids = ports
+        .inputs
+        .iter()
+        .map(|input| AnyParameterId::Input(*input))
+        .zip(
+            input_heights
+                .into_iter()
+                .map(|y| pos2(outer_rect.left(), y)),
+        )
+        .chain(
+            ports
+                .outputs
+                .iter()
+                .map(|output| AnyParameterId::Output(*output))
+                .zip(
+                    output_heights
+                        .into_iter()
+                        .map(|y| pos2(outer_rect.right(), y)),
+                ),
+        );
+    for (param_id, port_pos) in port_ids {
+        let color = graph
+            .any_param_type(param_id)
+            .unwrap()
+            .data_type_color(user_state);
+        ui.painter()
+            .circle_filled(port_pos, style.port_radius * zoom, color);
+        port_locations.insert(param_id, port_pos);
+    }
+
+    if window_response.double_clicked() {
+        action = Some(GroupAction::Open(Some(group_id)));
+    }
+    let drag_delta = window_response.drag_delta() / zoom;
+    if drag_delta.length_sq() > 0.0 {
+        action = Some(GroupAction::Move(group_id, drag_delta));
+    }
+    action
+}
+
+/// Draws the title bar of an expanded group, at the top of its `frame`.
+fn draw_group_frame_header(
+    ui: &mut Ui,
+    group_id: GroupId,
+    group: &NodeGroup,
+    frame: Rect,
+    zoom: f32,
+) -> Option<GroupAction> {
+    let header_rect =
+        Rect::from_min_size(frame.min, vec2(frame.width(), GROUP_HEADER_HEIGHT * zoom))
+            .shrink2(vec2(GROUP_FRAME_MARGIN * zoom, 0.0));
+    let mut header_ui = ui.child_ui_with_id_source(
+        header_rect,
+        Layout::left_to_right(Align::Center),
+        (group_id, "group_frame"),
+    );
+    header_ui.style_mut().scale(zoom);
+
+    let mut action = None;
+    header_ui.label(RichText::new(&group.label).text_style(TextStyle::Button));
+    if header_ui.small_button("Collapse").clicked() {
+        action = Some(GroupAction::SetCollapsed(group_id, true));
+    }
+    if header_ui.small_button("Enter").clicked() {
+        action = Some(GroupAction::Open(Some(group_id)));
+    }
+    if header_ui.small_button("Ungroup").clicked() {
+        action = Some(GroupAction::Ungroup(group_id));
+    }
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestState = GraphEditorState<(), (), (), (), ()>;
+
+    fn add_node(state: &mut TestState, x: f32) -> NodeId {
+        let node_id = state.graph.add_node(String::new(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                (),
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            graph.add_output_param(node_id, "out".into(), ());
+        });
+        state.node_positions.insert(node_id, egui::pos2(x, 0.0));
+        state.node_order.push(node_id);
+        node_id
+    }
+
+    fn connect(state: &mut TestState, from: NodeId, to: NodeId) {
+        let output = state.graph[from].get_output("out").unwrap();
+        let input = state.graph[to].get_input("in").unwrap();
+        state.graph.add_connection(output, input);
+    }
+
+    #[test]
+    pub fn test_group_ports_and_views() {
+        let mut state = TestState::default();
+        let [a, b, c, d] = [0.0, 10.0, 20.0, 30.0].map(|x| add_node(&mut state, x));
+        connect(&mut state, a, b);
+        connect(&mut state, b, c);
+        connect(&mut state, c, d);
+
+        let group = state.create_group(&[b, c], "group");
+        assert_eq!(state.groups[group].position, egui::pos2(10.0, 0.0));
+        assert_eq!(
+            state.group_ports(group),
+            GroupPorts {
+                inputs: vec![state.graph[b].get_input("in").unwrap()],
+                outputs: vec![state.graph[c].get_output("out").unwrap()],
+            }
+        );
+        assert_eq!(state.node_view(a), NodeView::Visible);
+        assert_eq!(state.node_view(b), NodeView::Collapsed(group));
+
+        // Nested groups are hidden by the outermost collapsed group.
+        state.open_group = Some(group);
+        assert_eq!(state.node_view(a), NodeView::Hidden);
+        assert_eq!(state.node_view(b), NodeView::Visible);
+        let inner = state.create_group(&[c], "inner");
+        assert_eq!(state.groups[inner].parent, Some(group));
+        assert_eq!(state.node_view(c), NodeView::Collapsed(inner));
+        state.open_group = None;
+        assert_eq!(state.node_view(c), NodeView::Collapsed(group));
+        assert_eq!(state.group_descendants(group), vec![b, c]);
+
+        // Grouping doesn't change the connections of the graph.
+        assert_eq!(state.graph.iter_connections().count(), 3);
+
+        state.ungroup(group);
+        assert_eq!(state.groups[inner].parent, None);
+        assert_eq!(state.node_view(b), NodeView::Visible);
+
+        state.graph.remove_node(c);
+        state.prune_groups();
+        assert!(state.groups.is_empty());
+    }
+}
@@ -0,0 +1,315 @@
+use super::*;
+use std::collections::VecDeque;
+
+/// The default value for [`UndoHistory::max_depth`].
+pub const DEFAULT_UNDO_DEPTH: usize = 100;
+
+/// Identifies a group of consecutive edits that are undone in a single step,
+/// like all the frames of a node drag, or all the keystrokes typed into an
+/// inline value widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UndoGroup {
+    /// One or more nodes being dragged with the mouse.
+    MoveNodes,
+    /// The selection being moved with the arrow keys.
+    NudgeSelection,
+    /// The inline value of an input parameter being edited.
+    EditValue(InputId),
+    /// The title of a comment frame being edited.
+    EditFrameTitle(FrameId),
+    /// The text of a sticky note being edited.
+    EditNoteText(NoteId),
+    /// A group defined by user code. The value is opaque to the library.
+    User(u64),
+}
+
+/// A copy of the parts of the editor state that are restored on undo / redo.
+#[derive(Debug, Clone)]
+pub struct EditorSnapshot<NodeData, DataType, ValueType> {
+    pub graph: Graph<NodeData, DataType, ValueType>,
+    pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
+    pub custom_node_sizes: SecondaryMap<NodeId, egui::Vec2>,
+    pub node_order: Vec<NodeId>,
+    pub collapsed_nodes: SecondaryMap<NodeId, ()>,
+    pub groups: SlotMap<GroupId, NodeGroup>,
+    pub frames: SlotMap<FrameId, CommentFrame>,
+    pub notes: SlotMap<NoteId, StickyNote>,
+    pub reroutes: Vec<WireReroutes>,
+}
+
+/// Undo / redo stacks for the [`GraphEditorState`]. Every entry is a snapshot
+/// of the editor state taken right before an undoable change.
+#[derive(Debug, Clone)]
+pub struct UndoHistory<NodeData, DataType, ValueType> {
+    pub undo_stack: VecDeque<EditorSnapshot<NodeData, DataType, ValueType>>,
+    pub redo_stack: Vec<EditorSnapshot<NodeData, DataType, ValueType>>,
+    /// The group of the most recent undo step, while it can still be extended
+    /// by further edits of the same group.
+    pub open_group: Option<UndoGroup>,
+    /// Maximum number of undo steps to keep. The oldest steps are discarded
+    /// first.
+    pub max_depth: usize,
+}
+
+impl<NodeData, DataType, ValueType> Default for UndoHistory<NodeData, DataType, ValueType> {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNDO_DEPTH)
+    }
+}
+
+impl<NodeData, DataType, ValueType> UndoHistory<NodeData, DataType, ValueType> {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            open_group: None,
+            max_depth,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    /// Discards all undo and redo steps.
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.open_group = None;
+    }
+
+    /// Closes the current group, so the next edit creates a new undo step
+    /// even when it belongs to the same group.
+    pub fn end_group(&mut self) {
+        self.open_group = None;
+    }
+
+    /// Records a new undo step, discarding the redo stack. When `group` is the
+    /// same as the currently open group, the change is merged into the
+    /// previous step and `snapshot` is never called.
+    pub fn record(
+        &mut self,
+        group: Option<UndoGroup>,
+        snapshot: impl FnOnce() -> EditorSnapshot<NodeData, DataType, ValueType>,
+    ) {
+        self.redo_stack.clear();
+        if group.is_some() && group == self.open_group {
+            return;
+        }
+
+        self.undo_stack.push_back(snapshot());
+        while self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+        self.open_group = group;
+    }
+
+    /// Pops the last undo step, pushing `current` to the redo stack.
+    pub fn undo(
+        &mut self,
+        current: EditorSnapshot<NodeData, DataType, ValueType>,
+    ) -> Option<EditorSnapshot<NodeData, DataType, ValueType>> {
+        let previous = self.undo_stack.pop_back()?;
+        self.redo_stack.push(current);
+        self.open_group = None;
+        Some(previous)
+    }
+
+    /// Pops the last redo step, pushing `current` to the undo stack.
+    pub fn redo(
+        &mut self,
+        current: EditorSnapshot<NodeData, DataType, ValueType>,
+    ) -> Option<EditorSnapshot<NodeData, DataType, ValueType>> {
+        let next = self.redo_stack.pop()?;
+        self.undo_stack.push_back(current);
+        self.open_group = None;
+        Some(next)
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Returns a copy of the undoable parts of the editor state.
+    pub fn snapshot(&self) -> EditorSnapshot<NodeData, DataType, ValueType> {
+        EditorSnapshot {
+            graph: self.graph.clone(),
+            node_positions: self.node_positions.clone(),
+            custom_node_sizes: self.custom_node_sizes.clone(),
+            node_order: self.node_order.clone(),
+            collapsed_nodes: self.collapsed_nodes.clone(),
+            groups: self.groups.clone(),
+            frames: self.frames.clone(),
+            notes: self.notes.clone(),
+            reroutes: self.reroutes.clone(),
+        }
+    }
+
+    /// Replaces the undoable parts of the editor state with `snapshot`. Any
+    /// ongoing interaction referencing removed nodes is cancelled.
+    pub fn restore_snapshot(&mut self, snapshot: EditorSnapshot<NodeData, DataType, ValueType>) {
+        self.graph = snapshot.graph;
+        self.node_positions = snapshot.node_positions;
+        self.custom_node_sizes = snapshot.custom_node_sizes;
+        self.node_order = snapshot.node_order;
+        self.collapsed_nodes = snapshot.collapsed_nodes;
+        self.groups = snapshot.groups;
+        self.frames = snapshot.frames;
+        self.notes = snapshot.notes;
+        self.reroutes = snapshot.reroutes;
+        if self
+            .open_group
+            .is_some_and(|group| !self.groups.contains_key(group))
+        {
+            self.open_group = None;
+        }
+
+        let graph = &self.graph;
+        self.selected_nodes
+            .retain(|id| graph.nodes.contains_key(*id));
+        let (frames, notes) = (&self.frames, &self.notes);
+        self.selected_frames.retain(|id| frames.contains_key(*id));
+        self.selected_notes.retain(|id| notes.contains_key(*id));
+        self.connection_in_progress = None;
+    }
+
+    /// Records the current state as an undo step. Applications should call
+    /// this right before mutating the graph from their own code, so the
+    /// change can be undone by the user. Consecutive calls with the same
+    /// `group` are merged into a single step.
+    pub fn record_undo_step(&mut self, group: Option<UndoGroup>) {
+        let mut history = std::mem::take(&mut self.undo_history);
+        history.record(group, || self.snapshot());
+        self.undo_history = history;
+    }
+
+    /// Runs `f` as a single undoable operation.
+    pub fn undoable<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.record_undo_step(None);
+        f(self)
+    }
+
+    /// Reverts the last undo step. Returns false when there was nothing to
+    /// undo.
+    pub fn undo(&mut self) -> bool {
+        if !self.undo_history.can_undo() {
+            return false;
+        }
+        let current = self.snapshot();
+        match self.undo_history.undo(current) {
+            Some(previous) => {
+                self.restore_snapshot(previous);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-applies the last undone step. Returns false when there was nothing
+    /// to redo.
+    pub fn redo(&mut self) -> bool {
+        if !self.undo_history.can_redo() {
+            return false;
+        }
+        let current = self.snapshot();
+        match self.undo_history.redo(current) {
+            Some(next) => {
+                self.restore_snapshot(next);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestHistory = UndoHistory<(), (), ()>;
+
+    fn snapshot_with_nodes(count: usize) -> EditorSnapshot<(), (), ()> {
+        let mut graph = Graph::new();
+        let node_order = (0..count)
+            .map(|_| graph.add_node(String::new(), (), |_, _| {}))
+            .collect();
+        EditorSnapshot {
+            graph,
+            node_positions: SecondaryMap::new(),
+            custom_node_sizes: SecondaryMap::new(),
+            node_order,
+            collapsed_nodes: SecondaryMap::new(),
+            groups: SlotMap::default(),
+            frames: SlotMap::default(),
+            notes: SlotMap::default(),
+            reroutes: Vec::new(),
+        }
+    }
+
+    #[test]
+    pub fn test_undo_redo_roundtrip() {
+        let mut history = TestHistory::default();
+        history.record(None, || snapshot_with_nodes(0));
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+
+        let previous = history.undo(snapshot_with_nodes(1)).unwrap();
+        assert_eq!(previous.node_order.len(), 0);
+        assert!(history.can_redo());
+
+        let next = history.redo(previous).unwrap();
+        assert_eq!(next.node_order.len(), 1);
+        assert!(history.undo(snapshot_with_nodes(1)).is_some());
+
+        // Recording a new step discards the redo stack.
+        history.record(None, || snapshot_with_nodes(0));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    pub fn test_groups_are_coalesced() {
+        let mut history = TestHistory::default();
+        history.record(Some(UndoGroup::MoveNodes), || snapshot_with_nodes(0));
+        history.record(Some(UndoGroup::MoveNodes), || unreachable!());
+        assert_eq!(history.undo_len(), 1);
+
+        history.end_group();
+        history.record(Some(UndoGroup::MoveNodes), || snapshot_with_nodes(0));
+        assert_eq!(history.undo_len(), 2);
+
+        // Ungrouped steps are never merged.
+        history.record(None, || snapshot_with_nodes(0));
+        history.record(None, || snapshot_with_nodes(0));
+        assert_eq!(history.undo_len(), 4);
+    }
+
+    #[test]
+    pub fn test_max_depth() {
+        let mut history = TestHistory::new(3);
+        for count in 0..5 {
+            history.record(None, || snapshot_with_nodes(count));
+        }
+        assert_eq!(history.undo_len(), 3);
+        let oldest = (0..3)
+            .map(|_| history.undo(snapshot_with_nodes(0)).unwrap())
+            .last()
+            .unwrap();
+        assert_eq!(oldest.node_order.len(), 2);
+    }
+}
@@ -1,6 +1,9 @@
 slotmap::new_key_type! { pub struct NodeId; }
 slotmap::new_key_type! { pub struct InputId; }
 slotmap::new_key_type! { pub struct OutputId; }
+slotmap::new_key_type! { pub struct GroupId; }
+slotmap::new_key_type! { pub struct FrameId; }
+slotmap::new_key_type! { pub struct NoteId; }
 
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
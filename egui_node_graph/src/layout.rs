@@ -0,0 +1,525 @@
+use super::*;
+use std::collections::HashMap;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The size assumed for nodes that have not been drawn yet.
+const DEFAULT_NODE_SIZE: egui::Vec2 = egui::vec2(200.0, 100.0);
+/// Number of passes of the crossing reduction heuristic.
+const ORDERING_ITERATIONS: usize = 12;
+
+/// Settings of the automatic layout. See [`GraphEditorState::auto_layout`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct LayoutSettings {
+    /// Horizontal space between two layers of nodes, in graph space.
+    pub layer_spacing: f32,
+    /// Vertical space between two nodes of the same layer, in graph space.
+    pub node_spacing: f32,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            layer_spacing: 80.0,
+            node_spacing: 30.0,
+        }
+    }
+}
+
+/// A side or center line that nodes can be aligned to. See
+/// [`GraphEditorState::align_nodes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Aligns the horizontal centers of the nodes, on a vertical line.
+    CenterHorizontal,
+    /// Aligns the vertical centers of the nodes, on a horizontal line.
+    CenterVertical,
+}
+
+/// The direction along which nodes are spread out. See
+/// [`GraphEditorState::distribute_nodes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    Horizontal,
+    Vertical,
+}
+
+/// A node of the layered graph. Connections spanning several layers go
+/// through a dummy item, with no node, in each layer they cross.
+struct LayoutItem {
+    node: Option<NodeId>,
+    size: egui::Vec2,
+    predecessors: Vec<usize>,
+    successors: Vec<usize>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Computes a layered layout of `nodes`, following the direction of the
+    /// connections from outputs to inputs. Returns the new position of each
+    /// node. The layout keeps the top-left corner of the area covered by
+    /// the nodes, and uses the node sizes measured when they were last drawn.
+    pub fn compute_layout(&self, nodes: &[NodeId]) -> SecondaryMap<NodeId, egui::Pos2> {
+        let settings = self.layout;
+        let index_of: HashMap<NodeId, usize> =
+            nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+        // Connections between the laid out nodes
+        let mut successors = vec![Vec::new(); nodes.len()];
+        for (input, output) in self.graph.iter_connections() {
+            let from = index_of.get(&self.graph[output].node);
+            let to = index_of.get(&self.graph[input].node);
+            if let (Some(from), Some(to)) = (from, to) {
+                if from != to && !successors[*from].contains(to) {
+                    successors[*from].push(*to);
+                }
+            }
+        }
+        let successors = remove_cycles(successors);
+        let layers = assign_layers(&successors);
+
+        let mut items: Vec<LayoutItem> = nodes
+            .iter()
+            .map(|node_id| LayoutItem {
+                node: Some(*node_id),
+                size: self.node_size(*node_id),
+                predecessors: Vec::new(),
+                successors: Vec::new(),
+            })
+            .collect();
+        let mut item_layers = layers.clone();
+        for (from, targets) in successors.iter().enumerate() {
+            for to in targets {
+                let mut previous = from;
+                for layer in layers[from] + 1..layers[*to] {
+                    items.push(LayoutItem {
+                        node: None,
+                        size: egui::Vec2::ZERO,
+                        predecessors: vec![previous],
+                        successors: Vec::new(),
+                    });
+                    item_layers.push(layer);
+                    let dummy = items.len() - 1;
+                    items[previous].successors.push(dummy);
+                    previous = dummy;
+                }
+                items[previous].successors.push(*to);
+                items[*to].predecessors.push(previous);
+            }
+        }
+
+        let layer_count = item_layers.iter().max().map_or(0, |max| max + 1);
+        let mut order: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+        for (item, layer) in item_layers.iter().enumerate() {
+            order[*layer].push(item);
+        }
+        let order = reduce_crossings(&items, order);
+
+        // Layers are placed from left to right, and the items of each layer
+        // are stacked from top to bottom, centered around the same line.
+        let mut positions = SecondaryMap::new();
+        let mut x = 0.0;
+        for layer in &order {
+            let height: f32 = layer
+                .iter()
+                .map(|item| items[*item].size.y + settings.node_spacing)
+                .sum();
+            let mut y = -height / 2.0;
+            for item in layer {
+                if let Some(node_id) = items[*item].node {
+                    positions.insert(node_id, egui::pos2(x, y));
+                }
+                y += items[*item].size.y + settings.node_spacing;
+            }
+            let width = layer
+                .iter()
+                .map(|item| items[*item].size.x)
+                .fold(0.0, f32::max);
+            x += width + settings.layer_spacing;
+        }
+
+        let old_origin = nodes
+            .iter()
+            .filter_map(|node_id| self.node_positions.get(*node_id).copied())
+            .reduce(egui::Pos2::min)
+            .unwrap_or_default();
+        let new_origin = positions
+            .values()
+            .copied()
+            .reduce(egui::Pos2::min)
+            .unwrap_or_default();
+        for pos in positions.values_mut() {
+            *pos += old_origin - new_origin;
+        }
+        positions
+    }
+
+    /// Moves `nodes` to a layered layout. See [`Self::compute_layout`].
+    pub fn auto_layout(&mut self, nodes: &[NodeId]) {
+        for (node_id, pos) in self.compute_layout(nodes) {
+            self.node_positions.insert(node_id, pos);
+        }
+    }
+
+    /// Returns the size of `node`, in graph space, measured when it was last
+    /// drawn.
+    pub fn node_size(&self, node: NodeId) -> egui::Vec2 {
+        self.node_sizes
+            .get(node)
+            .copied()
+            .unwrap_or(DEFAULT_NODE_SIZE)
+    }
+
+    /// Returns the area covered by `node`, in graph space.
+    pub fn node_rect(&self, node: NodeId) -> Option<egui::Rect> {
+        let pos = self.node_positions.get(node)?;
+        Some(egui::Rect::from_min_size(*pos, self.node_size(node)))
+    }
+
+    /// Moves `nodes` so that their sides or centers line up with the
+    /// outermost of them.
+    pub fn align_nodes(&mut self, nodes: &[NodeId], alignment: Alignment) {
+        let rects: Vec<(NodeId, egui::Rect)> = nodes
+            .iter()
+            .filter_map(|node_id| Some((*node_id, self.node_rect(*node_id)?)))
+            .collect();
+        let Some(bounds) = rects
+            .iter()
+            .map(|(_, rect)| *rect)
+            .reduce(egui::Rect::union)
+        else {
+            return;
+        };
+        for (node_id, rect) in rects {
+            let delta = match alignment {
+                Alignment::Left => egui::vec2(bounds.left() - rect.left(), 0.0),
+                Alignment::Right => egui::vec2(bounds.right() - rect.right(), 0.0),
+                Alignment::Top => egui::vec2(0.0, bounds.top() - rect.top()),
+                Alignment::Bottom => egui::vec2(0.0, bounds.bottom() - rect.bottom()),
+                Alignment::CenterHorizontal => egui::vec2(bounds.center().x - rect.center().x, 0.0),
+                Alignment::CenterVertical => egui::vec2(0.0, bounds.center().y - rect.center().y),
+            };
+            self.node_positions[node_id] += delta;
+        }
+    }
+
+    /// Moves `nodes` so that the gaps between them are equal. The first and
+    /// last nodes along the direction stay in place.
+    pub fn distribute_nodes(&mut self, nodes: &[NodeId], distribution: Distribution) {
+        let axis = match distribution {
+            Distribution::Horizontal => 0,
+            Distribution::Vertical => 1,
+        };
+        let mut rects: Vec<(NodeId, egui::Rect)> = nodes
+            .iter()
+            .filter_map(|node_id| Some((*node_id, self.node_rect(*node_id)?)))
+            .collect();
+        if rects.len() < 3 {
+            return;
+        }
+        rects.sort_by(|(_, a), (_, b)| a.center()[axis].total_cmp(&b.center()[axis]));
+
+        let start = rects[0].1.min[axis];
+        let end = rects[rects.len() - 1].1.max[axis];
+        let total_size: f32 = rects.iter().map(|(_, rect)| rect.size()[axis]).sum();
+        let gap = (end - start - total_size) / (rects.len() - 1) as f32;
+        let mut next = start;
+        for (node_id, rect) in rects {
+            self.node_positions[node_id][axis] += next - rect.min[axis];
+            next += rect.size()[axis] + gap;
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Lays out the selected nodes or, when nothing is selected, all the
+    /// visible nodes, as a single undoable step.
+    pub fn tidy_up(&mut self) {
+        let nodes = if self.selected_nodes.is_empty() {
+            self.node_order
+                .iter()
+                .copied()
+                .filter(|node_id| self.node_view(*node_id) == NodeView::Visible)
+                .collect()
+        } else {
+            self.selected_nodes.clone()
+        };
+        self.record_undo_step(None);
+        self.auto_layout(&nodes);
+    }
+
+    /// Aligns the selected nodes, as a single undoable step.
+    pub fn align_selection(&mut self, alignment: Alignment) {
+        let nodes = self.selected_nodes.clone();
+        self.record_undo_step(None);
+        self.align_nodes(&nodes, alignment);
+    }
+
+    /// Spreads out the selected nodes evenly, as a single undoable step.
+    pub fn distribute_selection(&mut self, distribution: Distribution) {
+        let nodes = self.selected_nodes.clone();
+        self.record_undo_step(None);
+        self.distribute_nodes(&nodes, distribution);
+    }
+}
+
+/// Reverses the connections going back to a node being visited in a depth
+/// first search, so that the layered graph has no cycles.
+fn remove_cycles(successors: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        New,
+        Active,
+        Done,
+    }
+    let mut visits = vec![Visit::New; successors.len()];
+    let mut acyclic = vec![Vec::new(); successors.len()];
+    for root in 0..successors.len() {
+        if visits[root] != Visit::New {
+            continue;
+        }
+        visits[root] = Visit::Active;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            match successors[node].get(*next) {
+                Some(&to) => {
+                    *next += 1;
+                    match visits[to] {
+                        Visit::Active => acyclic[to].push(node),
+                        Visit::New => {
+                            acyclic[node].push(to);
+                            visits[to] = Visit::Active;
+                            stack.push((to, 0));
+                        }
+                        Visit::Done => acyclic[node].push(to),
+                    }
+                }
+                None => {
+                    visits[node] = Visit::Done;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    for targets in acyclic.iter_mut() {
+        targets.sort_unstable();
+        targets.dedup();
+    }
+    acyclic
+}
+
+/// Puts each node one layer after the furthest of its predecessors.
+fn assign_layers(successors: &[Vec<usize>]) -> Vec<usize> {
+    let mut in_degree = vec![0; successors.len()];
+    for to in successors.iter().flatten() {
+        in_degree[*to] += 1;
+    }
+    let mut ready: Vec<usize> = (0..successors.len())
+        .filter(|node| in_degree[*node] == 0)
+        .collect();
+    let mut layers = vec![0; successors.len()];
+    while let Some(node) = ready.pop() {
+        for to in &successors[node] {
+            layers[*to] = layers[*to].max(layers[node] + 1);
+            in_degree[*to] -= 1;
+            if in_degree[*to] == 0 {
+                ready.push(*to);
+            }
+        }
+    }
+    layers
+}
+
+/// Reorders the items of each layer to reduce wire crossings, moving items
+/// to the average position of their neighbours in the adjacent layer.
+fn reduce_crossings(items: &[LayoutItem], mut order: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    let mut best = order.clone();
+    let mut best_crossings = count_crossings(items, &order);
+    for iteration in 0..ORDERING_ITERATIONS {
+        let downwards = iteration % 2 == 0;
+        let layers: Vec<usize> = if downwards {
+            (1..order.len()).collect()
+        } else {
+            (0..order.len().saturating_sub(1)).rev().collect()
+        };
+        for layer in layers {
+            let (adjacent, current) = if downwards {
+                let (before, after) = order.split_at_mut(layer);
+                (&before[layer - 1], &mut after[0])
+            } else {
+                let (before, after) = order.split_at_mut(layer + 1);
+                (&after[0], &mut before[layer])
+            };
+            let rank: HashMap<usize, usize> = adjacent
+                .iter()
+                .enumerate()
+                .map(|(i, item)| (*item, i))
+                .collect();
+            let barycenters: HashMap<usize, f32> = current
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let neighbours = if downwards {
+                        &items[*item].predecessors
+                    } else {
+                        &items[*item].successors
+                    };
+                    let barycenter = if neighbours.is_empty() {
+                        i as f32
+                    } else {
+                        neighbours.iter().map(|n| rank[n] as f32).sum::<f32>()
+                            / neighbours.len() as f32
+                    };
+                    (*item, barycenter)
+                })
+                .collect();
+            current.sort_by(|a, b| barycenters[a].total_cmp(&barycenters[b]));
+        }
+        let crossings = count_crossings(items, &order);
+        if crossings < best_crossings {
+            best = order.clone();
+            best_crossings = crossings;
+        }
+    }
+    best
+}
+
+/// Counts the pairs of connections crossing each other between adjacent
+/// layers.
+fn count_crossings(items: &[LayoutItem], order: &[Vec<usize>]) -> usize {
+    let mut crossings = 0;
+    for pair in order.windows(2) {
+        let rank: HashMap<usize, usize> = pair[1]
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (*item, i))
+            .collect();
+        let wires: Vec<(usize, usize)> = pair[0]
+            .iter()
+            .enumerate()
+            .flat_map(|(i, item)| items[*item].successors.iter().map(move |to| (i, to)))
+            .filter_map(|(i, to)| rank.get(to).map(|j| (i, *j)))
+            .collect();
+        for (a, first) in wires.iter().enumerate() {
+            for second in &wires[a + 1..] {
+                if (first.0 < second.0 && first.1 > second.1)
+                    || (first.0 > second.0 && first.1 < second.1)
+                {
+                    crossings += 1;
+                }
+            }
+        }
+    }
+    crossings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestState = GraphEditorState<(), (), (), (), ()>;
+
+    fn add_node(state: &mut TestState) -> NodeId {
+        let node_id = state.graph.add_node(String::new(), (), |graph, node_id| {
+            graph.add_wide_input_param(
+                node_id,
+                "in".into(),
+                (),
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+                None,
+            );
+            graph.add_output_param(node_id, "out".into(), ());
+        });
+        state.node_positions.insert(node_id, egui::Pos2::ZERO);
+        state.node_order.push(node_id);
+        node_id
+    }
+
+    fn connect(state: &mut TestState, from: NodeId, to: NodeId) {
+        let output = state.graph[from].get_output("out").unwrap();
+        let input = state.graph[to].get_input("in").unwrap();
+        state.graph.add_connection(output, input);
+    }
+
+    #[test]
+    pub fn test_layered_layout() {
+        let mut state = TestState::default();
+        let [a, b, c, d, e] = [(); 5].map(|_| add_node(&mut state));
+        connect(&mut state, a, c);
+        connect(&mut state, b, d);
+        connect(&mut state, c, e);
+        // A connection skipping a layer
+        connect(&mut state, a, e);
+        connect(&mut state, d, a);
+        connect(&mut state, e, b);
+
+        let positions = state.compute_layout(&[a, b, c, d, e]);
+        // The cycle is broken by reversing d -> a, and every other connection
+        // goes to the right.
+        let x = |node: NodeId| positions[node].x;
+        assert!(x(a) < x(c) && x(c) < x(e) && x(e) < x(b) && x(b) < x(d));
+        assert_eq!(
+            positions.values().copied().reduce(egui::Pos2::min),
+            Some(egui::Pos2::ZERO)
+        );
+
+        state.tidy_up();
+        assert_eq!(state.node_positions[a], positions[a]);
+        assert!(state.undo());
+        assert_eq!(state.node_positions[a], egui::Pos2::ZERO);
+    }
+
+    #[test]
+    pub fn test_align_and_distribute() {
+        let mut state = TestState::default();
+        let [a, b, c] = [(); 3].map(|_| add_node(&mut state));
+        for (node_id, x, width) in [(a, 0.0, 100.0), (b, 300.0, 50.0), (c, 150.0, 100.0)] {
+            state.node_positions[node_id] = egui::pos2(x, x / 10.0);
+            state.node_sizes.insert(node_id, egui::vec2(width, 40.0));
+        }
+
+        state.align_nodes(&[a, b, c], Alignment::Right);
+        assert_eq!(state.node_positions[a].x, 250.0);
+        assert_eq!(state.node_positions[b].x, 300.0);
+
+        state.align_nodes(&[a, b], Alignment::Top);
+        assert_eq!(state.node_positions[a].y, 0.0);
+        assert_eq!(state.node_positions[b].y, 0.0);
+
+        // The free space between the first and last nodes is split evenly
+        state.node_positions[b].y = 200.0;
+        state.node_positions[c].y = 60.0;
+        state.distribute_nodes(&[a, b, c], Distribution::Vertical);
+        assert_eq!(state.node_positions[a].y, 0.0);
+        assert_eq!(state.node_positions[c].y, 100.0);
+        assert_eq!(state.node_positions[b].y, 200.0);
+    }
+
+    #[test]
+    pub fn test_reduce_crossings() {
+        let mut state = TestState::default();
+        let [a, b, c, d] = [(); 4].map(|_| add_node(&mut state));
+        connect(&mut state, a, d);
+        connect(&mut state, b, c);
+
+        let positions = state.compute_layout(&[a, b, c, d]);
+        let y = |node: NodeId| positions[node].y;
+        assert_eq!(y(a) < y(b), y(d) < y(c));
+    }
+}
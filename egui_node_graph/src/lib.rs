@@ -28,6 +28,50 @@ pub use error::*;
 pub mod ui_state;
 pub use ui_state::*;
 
+/// Undo / redo support for the editor
+pub mod history;
+pub use history::*;
+
+/// Grouping of nodes into collapsible subgraphs
+pub mod groups;
+pub use groups::*;
+
+/// Comment frames and sticky notes drawn on the canvas
+pub mod annotations;
+pub use annotations::*;
+
+/// Reroute points on connections
+pub mod reroutes;
+pub use reroutes::*;
+
+/// The background grid and snapping nodes to it
+pub mod grid;
+pub use grid::*;
+
+/// Automatic layout of the nodes
+pub mod layout;
+pub use layout::*;
+
+/// Moving the view to show the graph, the selection or a node
+pub mod navigation;
+pub use navigation::*;
+
+/// Shapes and strokes of the wires drawn for connections
+pub mod wires;
+pub use wires::*;
+
+/// Colors and measurements used to draw the editor
+pub mod style;
+pub use style::*;
+
+/// An overview of the graph, used to navigate large graphs
+pub mod minimap;
+pub use minimap::*;
+
+/// Copy and paste of nodes
+pub mod clipboard;
+pub use clipboard::*;
+
 /// The node finder is a tiny widget allowing to create new node types
 pub mod node_finder;
 pub use node_finder::*;
@@ -43,4 +87,7 @@ pub use traits::*;
 
 mod utils;
 
+/// Scaling of egui styles, used to draw nodes at the current zoom level
+mod scale;
+
 mod color_hex_utils;
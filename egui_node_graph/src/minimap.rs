@@ -0,0 +1,204 @@
+use super::*;
+use egui::emath::RectTransform;
+use egui::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Space between the minimap and the border of the editor.
+const MINIMAP_MARGIN: f32 = 10.0;
+/// Space around the nodes shown in the minimap, in graph space.
+const MINIMAP_PADDING: f32 = 50.0;
+
+/// A small overview of the whole graph, drawn in a corner of the editor.
+/// Clicking or dragging on it moves the view. See
+/// [`GraphEditorState::minimap`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct Minimap {
+    /// The corner of the editor the minimap is drawn in.
+    pub anchor: Align2,
+    pub size: Vec2,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self {
+            anchor: Align2::RIGHT_BOTTOM,
+            size: vec2(200.0, 150.0),
+        }
+    }
+}
+
+impl Minimap {
+    /// Returns the area covered by the minimap, given the area of the editor.
+    pub fn rect(&self, editor_rect: Rect) -> Rect {
+        self.anchor
+            .align_size_within_rect(self.size, editor_rect.shrink(MINIMAP_MARGIN))
+    }
+
+    /// Draws the minimap over the editor. `nodes` are the rects of the drawn
+    /// nodes and `wires` the endpoints and colors of the drawn connections,
+    /// all in graph space. Moves the view when the minimap is clicked or
+    /// dragged.
+    ///
+    /// The minimap is drawn in its own foreground area, so it takes the clicks
+    /// and drags over the nodes and wires below it.
+    pub(crate) fn show(
+        &self,
+        ui: &Ui,
+        editor_rect: Rect,
+        pan_zoom: &mut PanZoom,
+        nodes: &[Rect],
+        wires: &[(Pos2, Pos2, Color32)],
+    ) -> Response {
+        let map_rect = self.rect(editor_rect);
+        let viewport = Rect::from_min_max(
+            pan_zoom.screen_to_graph(editor_rect.min, editor_rect.min),
+            pan_zoom.screen_to_graph(editor_rect.min, editor_rect.max),
+        );
+        let content = match nodes.iter().copied().reduce(Rect::union) {
+            Some(bounds) => bounds.expand(MINIMAP_PADDING),
+            None => viewport,
+        };
+        let to_map = fit_transform(content, map_rect.shrink(4.0));
+        let clip_rect = ui.clip_rect();
+
+        Area::new(ui.id().with("minimap"))
+            .order(Order::Foreground)
+            .fixed_pos(map_rect.min)
+            .show(ui.ctx(), |ui| {
+                ui.set_clip_rect(clip_rect);
+                let response = ui.allocate_rect(map_rect, Sense::click_and_drag());
+                if response.clicked() || response.dragged() {
+                    if let Some(pointer) = response.interact_pointer_pos() {
+                        // Center the view on the clicked point
+                        let target = to_map.inverse().transform_pos(pointer);
+                        pan_zoom.pan = editor_rect.size() / 2.0 - target.to_vec2() * pan_zoom.zoom;
+                    }
+                }
+
+                let visuals = ui.visuals();
+                let painter = ui.painter().with_clip_rect(map_rect);
+                painter.rect(
+                    map_rect,
+                    4.0,
+                    visuals.extreme_bg_color.gamma_multiply(0.9),
+                    visuals.window_stroke(),
+                );
+                for (src, dst, color) in wires {
+                    painter.line_segment(
+                        [to_map.transform_pos(*src), to_map.transform_pos(*dst)],
+                        Stroke::new(1.0, *color),
+                    );
+                }
+                for node in nodes {
+                    painter.rect_filled(
+                        to_map.transform_rect(*node),
+                        1.0,
+                        visuals.text_color().gamma_multiply(0.5),
+                    );
+                }
+                painter.rect_stroke(
+                    to_map.transform_rect(viewport),
+                    0.0,
+                    visuals.selection.stroke,
+                );
+
+                response
+            })
+            .inner
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    /// Draws the minimap, when enabled, from the nodes and wires drawn this
+    /// frame. Returns whether the cursor is over the minimap.
+    pub(crate) fn draw_minimap(
+        &mut self,
+        ui: &mut Ui,
+        editor_rect: Rect,
+        node_rects: &NodeRects,
+        port_locations: &PortLocations,
+        user_state: &mut UserState,
+    ) -> bool {
+        let mut cursor_in_minimap = false;
+        if let Some(minimap) = self.minimap {
+            let to_graph = |pos: Pos2| self.pan_zoom.screen_to_graph(editor_rect.min, pos);
+            let nodes: Vec<Rect> = node_rects
+                .values()
+                .map(|rect| Rect::from_min_max(to_graph(rect.min), to_graph(rect.max)))
+                .collect();
+            let wires: Vec<(Pos2, Pos2, Color32)> = self
+                .graph
+                .iter_connections()
+                .filter_map(|(input, output)| {
+                    let src_pos = port_locations.get(&AnyParameterId::Output(output))?;
+                    let dst_pos = port_locations.get(&AnyParameterId::Input(input))?;
+                    let color = self
+                        .graph
+                        .get_output(output)
+                        .typ
+                        .data_type_color(user_state);
+                    Some((to_graph(*src_pos), to_graph(*dst_pos), color))
+                })
+                .collect();
+            let minimap_response =
+                minimap.show(ui, editor_rect, &mut self.pan_zoom, &nodes, &wires);
+            if minimap_response.clicked() || minimap_response.dragged() {
+                self.view_animation = None;
+            }
+            cursor_in_minimap = minimap_response.hovered();
+        }
+        cursor_in_minimap
+    }
+}
+
+/// Returns the transform that scales `content` uniformly to fit inside
+/// `target`, centered.
+fn fit_transform(content: Rect, target: Rect) -> RectTransform {
+    // An empty graph in a collapsed editor gives zero-size rects, which would
+    // make the transform divide by zero.
+    let content = Rect::from_center_size(content.center(), content.size().max(Vec2::splat(1.0)));
+    let target = Rect::from_center_size(target.center(), target.size().max(Vec2::splat(1.0)));
+    let scale = (target.width() / content.width()).min(target.height() / content.height());
+    let fitted = Rect::from_center_size(target.center(), content.size() * scale);
+    RectTransform::from_to(content, fitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_fit_transform() {
+        let content = Rect::from_min_size(pos2(-100.0, 0.0), vec2(400.0, 100.0));
+        let target = Rect::from_min_size(pos2(0.0, 0.0), vec2(200.0, 200.0));
+        let transform = fit_transform(content, target);
+
+        // The aspect ratio is kept, and the content is centered vertically
+        assert_eq!(transform.transform_pos(content.min), pos2(0.0, 75.0));
+        assert_eq!(transform.transform_pos(content.max), pos2(200.0, 125.0));
+        assert_eq!(
+            transform.inverse().transform_pos(target.center()),
+            content.center()
+        );
+    }
+
+    #[test]
+    pub fn test_fit_transform_degenerate() {
+        let point = Rect::from_min_size(pos2(10.0, 20.0), Vec2::ZERO);
+        // A tiny editor shrinks to a negative size
+        let negative = Rect::from_min_max(pos2(0.0, 0.0), pos2(-5.0, -5.0));
+        for (content, target) in [(point, point), (point, negative)] {
+            let transform = fit_transform(content, target);
+            let inverse = transform.inverse();
+            assert!(transform.transform_pos(content.min).x.is_finite());
+            assert!(inverse.transform_pos(target.center()).y.is_finite());
+        }
+    }
+}
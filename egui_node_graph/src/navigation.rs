@@ -0,0 +1,208 @@
+use super::*;
+
+/// Space left between the framed nodes and the border of the editor, in
+/// screen units.
+const FRAME_MARGIN: f32 = 40.0;
+/// Duration of animated view changes, in seconds.
+const VIEW_ANIMATION_DURATION: f32 = 0.3;
+
+/// A change of the view requested by [`GraphEditorState::frame_all`],
+/// [`GraphEditorState::frame_selection`] or [`GraphEditorState::focus_node`].
+/// Requests are applied the next time the editor is drawn, once the size of
+/// the editor is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewRequest {
+    FrameAll,
+    FrameSelection,
+    FocusNode(NodeId),
+}
+
+/// An ongoing animated transition of the view.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewAnimation {
+    pub from: PanZoom,
+    pub to: PanZoom,
+    /// Goes from 0.0 at the start of the animation to 1.0 at the end.
+    pub progress: f32,
+}
+
+impl PanZoom {
+    /// Returns the view showing all of `rect`, in graph space, centered in an
+    /// editor of size `editor_size`. The zoom is clamped to the
+    /// `zoom_min..=zoom_max` range, and never goes above 1.0 so that small
+    /// graphs are shown at their natural size.
+    pub fn fit_rect(
+        rect: egui::Rect,
+        editor_size: egui::Vec2,
+        zoom_min: f32,
+        zoom_max: f32,
+    ) -> Self {
+        let available =
+            (editor_size - egui::Vec2::splat(2.0 * FRAME_MARGIN)).max(egui::Vec2::splat(1.0));
+        let zoom = (available.x / rect.width().max(1.0))
+            .min(available.y / rect.height().max(1.0))
+            .min(1.0)
+            .clamp(zoom_min, zoom_max);
+        Self::centered_on(rect.center(), zoom, editor_size)
+    }
+
+    /// Returns the view with the given `zoom` that shows `pos`, in graph
+    /// space, at the center of an editor of size `editor_size`.
+    pub fn centered_on(pos: egui::Pos2, zoom: f32, editor_size: egui::Vec2) -> Self {
+        Self {
+            pan: editor_size / 2.0 - pos.to_vec2() * zoom,
+            zoom,
+        }
+    }
+
+    /// Returns the position, in graph space, shown at the center of an editor
+    /// of size `editor_size`.
+    pub fn center(&self, editor_size: egui::Vec2) -> egui::Pos2 {
+        ((editor_size / 2.0 - self.pan) / self.zoom).to_pos2()
+    }
+}
+
+impl ViewAnimation {
+    /// Returns the view at the current progress of the animation. The zoom
+    /// is interpolated geometrically, and the center of the view moves in a
+    /// straight line.
+    pub fn current(&self, editor_size: egui::Vec2) -> PanZoom {
+        let t = self.progress.clamp(0.0, 1.0);
+        let t = t * t * (3.0 - 2.0 * t);
+        let zoom = self.from.zoom * (self.to.zoom / self.from.zoom).powf(t);
+        let center = self
+            .from
+            .center(editor_size)
+            .lerp(self.to.center(editor_size), t);
+        PanZoom::centered_on(center, zoom, editor_size)
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Fits all the visible nodes and comment frames in the editor.
+    pub fn frame_all(&mut self) {
+        self.view_request = Some(ViewRequest::FrameAll);
+    }
+
+    /// Fits the selected nodes, comment frames and notes in the editor. Frames
+    /// everything when nothing is selected.
+    pub fn frame_selection(&mut self) {
+        self.view_request = Some(ViewRequest::FrameSelection);
+    }
+
+    /// Centers the editor on `node`, keeping the current zoom. When the node
+    /// is hidden by a group, the group containing it is entered.
+    pub fn focus_node(&mut self, node: NodeId) {
+        self.view_request = Some(ViewRequest::FocusNode(node));
+    }
+
+    /// Returns the view to move to for `request`, in an editor of size
+    /// `editor_size`. Returns `None` when there is nothing to show.
+    pub(crate) fn resolve_view_request(
+        &mut self,
+        request: ViewRequest,
+        editor_size: egui::Vec2,
+    ) -> Option<PanZoom> {
+        let rect = match request {
+            ViewRequest::FocusNode(node) => {
+                if !self.graph.nodes.contains_key(node) {
+                    return None;
+                }
+                if self.node_view(node) != NodeView::Visible {
+                    self.open_group = self.node_groups().get(node).copied();
+                }
+                let center = self.node_rect(node)?.center();
+                return Some(PanZoom::centered_on(
+                    center,
+                    self.pan_zoom.zoom,
+                    editor_size,
+                ));
+            }
+            ViewRequest::FrameSelection
+                if !self.selected_nodes.is_empty()
+                    || !self.selected_frames.is_empty()
+                    || !self.selected_notes.is_empty() =>
+            {
+                self.selected_nodes
+                    .iter()
+                    .filter_map(|node_id| self.node_rect(*node_id))
+                    .chain(self.selected_frames.iter().map(|id| self.frames[*id].rect))
+                    .chain(self.selected_notes.iter().map(|id| {
+                        egui::Rect::from_min_size(self.notes[*id].position, egui::Vec2::ZERO)
+                    }))
+                    .reduce(egui::Rect::union)
+            }
+            ViewRequest::FrameAll | ViewRequest::FrameSelection => self
+                .node_order
+                .iter()
+                .copied()
+                .filter(|node_id| self.node_view(*node_id) == NodeView::Visible)
+                .filter_map(|node_id| self.node_rect(node_id))
+                .chain(self.frames.values().map(|frame| frame.rect))
+                .reduce(egui::Rect::union),
+        }?;
+        Some(PanZoom::fit_rect(
+            rect,
+            editor_size,
+            self.min_zoom,
+            self.max_zoom,
+        ))
+    }
+
+    /// Moves the view to `target`, animated when
+    /// [`GraphEditorState::animate_view`] is set.
+    pub(crate) fn move_view(&mut self, target: PanZoom) {
+        if self.animate_view {
+            self.view_animation = Some(ViewAnimation {
+                from: self.pan_zoom,
+                to: target,
+                progress: 0.0,
+            });
+        } else {
+            self.pan_zoom = target;
+        }
+    }
+
+    /// Advances the ongoing view animation by `dt` seconds. Returns true while
+    /// the animation is running.
+    pub(crate) fn advance_view_animation(&mut self, dt: f32, editor_size: egui::Vec2) -> bool {
+        let Some(animation) = self.view_animation.as_mut() else {
+            return false;
+        };
+        animation.progress += dt / VIEW_ANIMATION_DURATION;
+        self.pan_zoom = animation.current(editor_size);
+        if animation.progress >= 1.0 {
+            self.pan_zoom = animation.to;
+            self.view_animation = None;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_fit_rect() {
+        let editor_size = egui::vec2(880.0, 480.0);
+        let rect = egui::Rect::from_min_size(egui::pos2(100.0, 100.0), egui::vec2(1600.0, 200.0));
+        let view = PanZoom::fit_rect(rect, editor_size, 0.1, 3.0);
+        assert_eq!(view.zoom, 0.5);
+        assert_eq!(view.center(editor_size), rect.center());
+
+        // Small graphs are not zoomed in
+        let small = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(10.0, 10.0));
+        assert_eq!(PanZoom::fit_rect(small, editor_size, 0.1, 3.0).zoom, 1.0);
+
+        let animation = ViewAnimation {
+            from: PanZoom::default(),
+            to: view,
+            progress: 0.5,
+        };
+        let halfway = animation.current(editor_size);
+        assert!((halfway.zoom - 0.5f32.sqrt()).abs() < 1e-6);
+    }
+}
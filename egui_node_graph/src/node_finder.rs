@@ -1,9 +1,32 @@
-use std::{collections::BTreeMap, marker::PhantomData};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    marker::PhantomData,
+};
 
-use crate::{color_hex_utils::*, CategoryTrait, NodeTemplateIter, NodeTemplateTrait};
+use crate::{
+    AnyParameterId, CategoryTrait, DataTypeTrait, GraphStyle, NodeTemplateIter, NodeTemplateTrait,
+    TemplatePort, TemplatePorts,
+};
 
+use egui::text::{LayoutJob, TextFormat};
 use egui::*;
 
+/// Score of every matched character in [`fuzzy_match`].
+const MATCH_SCORE: i32 = 10;
+/// Extra score of characters matched at the start of a word.
+const WORD_START_BONUS: i32 = 20;
+/// Extra score of characters matched right after the previous one.
+const CONSECUTIVE_BONUS: i32 = 20;
+/// Maximum penalty for the characters skipped between two matches, or before
+/// the first one. Each skipped character costs one point.
+const MAX_GAP_PENALTY: i32 = 10;
+/// Number of entries page up and page down move the highlight by.
+const FINDER_PAGE_SIZE: usize = 10;
+/// Number of templates kept in [`NodeFinderSettings::recent`].
+const MAX_RECENT: usize = 8;
+/// Width of the pane describing the highlighted template.
+const PREVIEW_WIDTH: f32 = 180.0;
+
 #[derive(Clone)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeFinder<NodeTemplate> {
@@ -11,13 +34,290 @@ pub struct NodeFinder<NodeTemplate> {
     /// Reset every frame. When set, the node finder will be moved at that position
     pub position: Option<Pos2>,
     pub just_spawned: bool,
+    /// Index of the highlighted entry, among the visible categories and
+    /// templates.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub current: usize,
+    /// Names of the expanded categories.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub open_categories: HashSet<String>,
+    /// When the finder was opened by dropping a wire on empty space, the port
+    /// the wire was dragged from. The created node gets connected to it.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub wire_origin: Option<AnyParameterId>,
+    /// Where the wire was dropped, in graph space. The created node is placed
+    /// there.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub drop_position: Option<Pos2>,
+    /// The templates offered instead of all of them, when opened from a wire.
+    #[cfg_attr(feature = "persistence", serde(skip, default = "Option::default"))]
+    pub compatible_kinds: Option<Vec<NodeTemplate>>,
     _phantom: PhantomData<NodeTemplate>,
 }
 
-impl<NodeTemplate, NodeData, UserState, CategoryType> NodeFinder<NodeTemplate>
+/// A row of the node finder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FinderEntry {
+    /// The header of a category, by index in the list of categories.
+    Category(usize),
+    /// A template, by index in the search results. Templates in several
+    /// categories have one entry in each.
+    Template {
+        category: Option<usize>,
+        index: usize,
+    },
+    /// A template of the "Favorites" section, by index in the search results.
+    Favorite(usize),
+    /// A template of the "Recent" section, by index in the search results.
+    Recent(usize),
+}
+
+/// The ports of the templates, built once per template with
+/// [`NodeTemplateTrait::template_ports`] and keyed by their
+/// [`NodeTemplateTrait::node_finder_label`]. See
+/// [`crate::GraphEditorState::template_ports`].
+#[derive(Debug, Clone)]
+pub struct TemplatePortsCache<DataType> {
+    ports: HashMap<String, TemplatePorts<DataType>>,
+}
+
+impl<DataType> Default for TemplatePortsCache<DataType> {
+    fn default() -> Self {
+        Self {
+            ports: HashMap::new(),
+        }
+    }
+}
+
+impl<DataType> TemplatePortsCache<DataType> {
+    /// Returns the ports of `kind`, building them on first use.
+    pub fn get<NodeTemplate>(
+        &mut self,
+        kind: &NodeTemplate,
+        user_state: &mut NodeTemplate::UserState,
+    ) -> &TemplatePorts<DataType>
+    where
+        NodeTemplate: NodeTemplateTrait<DataType = DataType>,
+    {
+        let label = kind.node_finder_label(user_state).into_owned();
+        self.ports
+            .entry(label)
+            .or_insert_with(|| kind.template_ports(user_state))
+    }
+
+    /// Forgets the ports of all templates, so they are built again. Call this
+    /// when the ports of the templates change.
+    pub fn clear(&mut self) {
+        self.ports.clear();
+    }
+}
+
+/// Options of the node finder kept while it is closed. See
+/// [`crate::GraphEditorState::node_finder_settings`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeFinderSettings {
+    /// While searching, shows a single list of results ranked by relevance
+    /// instead of the category tree.
+    pub flat_results: bool,
+    /// Labels of the last created templates, most recently used first.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub recent: Vec<String>,
+    /// Labels of the templates pinned by the user, in the order they were
+    /// pinned.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub favorites: Vec<String>,
+}
+
+impl NodeFinderSettings {
+    /// Moves `label` to the front of [`Self::recent`].
+    pub fn record_use(&mut self, label: &str) {
+        self.recent.retain(|recent| recent != label);
+        self.recent.insert(0, label.to_owned());
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    pub fn is_favorite(&self, label: &str) -> bool {
+        self.favorites.iter().any(|favorite| favorite == label)
+    }
+
+    /// Pins `label` to the favorites, or unpins it if it was pinned.
+    pub fn toggle_favorite(&mut self, label: &str) {
+        if self.is_favorite(label) {
+            self.favorites.retain(|favorite| favorite != label);
+        } else {
+            self.favorites.push(label.to_owned());
+        }
+    }
+}
+
+/// The result of [`fuzzy_match`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// How well the query matches. Higher is better.
+    pub score: i32,
+    /// Indices of the matched characters of the text, in increasing order.
+    /// These are char indices, not byte indices.
+    pub matched: Vec<usize>,
+}
+
+/// Matches `query` against `text`, ignoring case. Every word of the query must
+/// appear in `text` as a subsequence of its characters, in any order, so
+/// "gblr" and "blur gaus" both match "Gaussian Blur". Matches at the start of
+/// words and runs of consecutive characters score higher. An empty query
+/// matches everything with a score of 0.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let text: Vec<char> = text.chars().collect();
+    let mut result = FuzzyMatch::default();
+    for word in query.split_whitespace() {
+        let word: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+        let (score, matched) = match_word(&word, &text)?;
+        result.score += score;
+        result.matched.extend(matched);
+    }
+    result.matched.sort_unstable();
+    result.matched.dedup();
+    Some(result)
+}
+
+/// Finds the best scoring way to match the lowercase `word` as a subsequence
+/// of `text`. Returns the score and the indices of the matched characters.
+fn match_word(word: &[char], text: &[char]) -> Option<(i32, Vec<usize>)> {
+    if word.is_empty() || word.len() > text.len() {
+        return None;
+    }
+    let lower: Vec<char> = text
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let char_score = |j: usize| {
+        let word_start = j == 0
+            || !text[j - 1].is_alphanumeric()
+            || (text[j - 1].is_lowercase() && text[j].is_uppercase());
+        MATCH_SCORE + if word_start { WORD_START_BONUS } else { 0 }
+    };
+    let gap_penalty = |gap: usize| (gap as i32).min(MAX_GAP_PENALTY);
+
+    // best[i][j] is the best score of matching word[..=i] with word[i] at
+    // text[j], along with the position of word[i - 1].
+    let mut best: Vec<Vec<Option<(i32, usize)>>> = vec![vec![None; text.len()]; word.len()];
+    for (j, c) in lower.iter().enumerate() {
+        if *c == word[0] {
+            best[0][j] = Some((char_score(j) - gap_penalty(j), 0));
+        }
+    }
+    for i in 1..word.len() {
+        for j in i..text.len() {
+            if lower[j] != word[i] {
+                continue;
+            }
+            best[i][j] = (i - 1..j)
+                .filter_map(|k| {
+                    let (score, _) = best[i - 1][k]?;
+                    let link = if k + 1 == j {
+                        CONSECUTIVE_BONUS
+                    } else {
+                        -gap_penalty(j - k - 1)
+                    };
+                    Some((score + link + char_score(j), k))
+                })
+                .max_by_key(|(score, _)| *score);
+        }
+    }
+
+    let last = word.len() - 1;
+    let (mut j, score) = (0..text.len())
+        .filter_map(|j| best[last][j].map(|(score, _)| (j, score)))
+        .max_by_key(|(_, score)| *score)?;
+    let mut matched = vec![0; word.len()];
+    for i in (0..word.len()).rev() {
+        matched[i] = j;
+        j = best[i][j].map_or(0, |(_, previous)| previous);
+    }
+    Some((score, matched))
+}
+
+/// Shows the icon, description and `ports` of `kind`.
+fn template_preview<NodeTemplate, UserState>(
+    ui: &mut Ui,
+    kind: &NodeTemplate,
+    ports: &TemplatePorts<NodeTemplate::DataType>,
+    style: &GraphStyle,
+    user_state: &mut UserState,
+) where
+    NodeTemplate: NodeTemplateTrait<UserState = UserState>,
+    NodeTemplate::DataType: DataTypeTrait<UserState>,
+{
+    ui.vertical(|ui| {
+        ui.set_width(PREVIEW_WIDTH);
+        let label = kind.node_finder_label(user_state).into_owned();
+        let title = match kind.node_finder_icon(user_state) {
+            Some(icon) => format!("{icon} {label}"),
+            None => label,
+        };
+        ui.label(RichText::new(title).strong());
+        if let Some(description) = kind.node_finder_description(user_state) {
+            ui.label(description.into_owned());
+        }
+
+        let mut port_list =
+            |ui: &mut Ui, heading: &str, ports: &[TemplatePort<NodeTemplate::DataType>]| {
+                if ports.is_empty() {
+                    return;
+                }
+                ui.add_space(5.0);
+                ui.label(RichText::new(heading).weak());
+                for port in ports {
+                    ui.horizontal(|ui| {
+                        let size = Vec2::splat(2.0 * style.port_radius);
+                        let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+                        let color = port.typ.data_type_color(user_state);
+                        ui.painter()
+                            .circle_filled(rect.center(), style.port_radius, color);
+                        ui.label(&port.name);
+                        ui.label(RichText::new(port.typ.name()).weak());
+                    });
+                }
+            };
+        port_list(ui, "Inputs", &ports.inputs);
+        port_list(ui, "Outputs", &ports.outputs);
+    });
+}
+
+/// Returns `label` with the `matched` characters drawn in `highlight`.
+fn highlighted_label(ui: &Ui, label: &str, matched: &[usize], highlight: Color32) -> WidgetText {
+    let font_id = TextStyle::Button.resolve(ui.style());
+    let normal = TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+    let highlighted = TextFormat {
+        underline: Stroke::new(1.0, highlight),
+        ..TextFormat::simple(font_id, highlight)
+    };
+    let mut job = LayoutJob::default();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (index, c) in label.chars().enumerate() {
+        let is_matched = matched.binary_search(&index).is_ok();
+        if is_matched != run_matched && !run.is_empty() {
+            let format = if run_matched { &highlighted } else { &normal };
+            job.append(&std::mem::take(&mut run), 0.0, format.clone());
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    let format = if run_matched { highlighted } else { normal };
+    job.append(&run, 0.0, format);
+    job.into()
+}
+
+impl<NodeTemplate, NodeData, DataType, UserState, CategoryType> NodeFinder<NodeTemplate>
 where
-    NodeTemplate:
-        NodeTemplateTrait<NodeData = NodeData, UserState = UserState, CategoryType = CategoryType>,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState>,
     CategoryType: CategoryTrait,
 {
     pub fn new_at(pos: Pos2) -> Self {
@@ -25,127 +325,459 @@ where
             query: "".into(),
             position: Some(pos),
             just_spawned: true,
+            current: 0,
+            open_categories: Default::default(),
+            wire_origin: None,
+            drop_position: None,
+            compatible_kinds: None,
             _phantom: Default::default(),
         }
     }
 
-    /// Shows the node selector panel with a search bar. Returns whether a node
-    /// archetype was selected and, in that case, the finder should be hidden on
-    /// the next frame.
+    /// Opens the finder at `pos` for a wire dragged from `origin` and dropped
+    /// at `drop_position`, in graph space, offering only `compatible_kinds`.
+    /// See [`NodeTemplateTrait::template_ports`].
+    pub fn new_for_wire(
+        pos: Pos2,
+        origin: AnyParameterId,
+        drop_position: Pos2,
+        compatible_kinds: Vec<NodeTemplate>,
+    ) -> Self {
+        NodeFinder {
+            wire_origin: Some(origin),
+            drop_position: Some(drop_position),
+            compatible_kinds: Some(compatible_kinds),
+            ..Self::new_at(pos)
+        }
+    }
+
+    /// Shows the node selector panel with a search bar, drawn with the finder
+    /// colors of `style`. Returns whether a node archetype was selected and, in
+    /// that case, the finder should be hidden on the next frame.
+    ///
+    /// The highlighted entry is moved with the up, down, page up and page down
+    /// keys, and Enter creates the highlighted template. Tab expands or
+    /// collapses the highlighted category, or collapses the category of the
+    /// highlighted template. The right and left keys expand and collapse the
+    /// highlighted category.
+    ///
+    /// While the query is empty, the templates pinned in `settings` and the
+    /// recently created ones are listed first. Templates are identified by
+    /// their [`NodeTemplateTrait::node_finder_label`].
+    ///
+    /// Templates whose label doesn't match the query can still be found by
+    /// their [`NodeTemplateTrait::node_finder_keywords`], ranked below the
+    /// label matches. The hovered or highlighted template is described in a
+    /// pane next to the results, with its ports taken from `template_ports`.
     pub fn show(
         &mut self,
         ui: &mut Ui,
         all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        style: &GraphStyle,
+        settings: &mut NodeFinderSettings,
+        template_ports: &mut TemplatePortsCache<DataType>,
         user_state: &mut UserState,
     ) -> Option<NodeTemplate> {
-        let background_color;
-        let text_color;
-
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#fefefe").unwrap();
-            text_color = color_from_hex("#3f3f3f").unwrap();
-        }
-
-        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, text_color);
+        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, style.finder_text);
 
         let frame = Frame::dark_canvas(ui.style())
-            .fill(background_color)
+            .fill(style.finder_background)
             .inner_margin(vec2(5.0, 5.0));
 
+        // The navigation keys are taken before the search bar sees them.
+        let (down, up, page_down, page_up, tab, right, left) = ui.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::PageDown),
+                i.consume_key(Modifiers::NONE, Key::PageUp),
+                i.consume_key(Modifiers::NONE, Key::Tab),
+                i.key_pressed(Key::ArrowRight),
+                i.key_pressed(Key::ArrowLeft),
+            )
+        });
+
         // The archetype that will be returned.
         let mut submitted_archetype = None;
+        let mut toggled_favorite = None;
         frame.show(ui, |ui| {
             ui.vertical(|ui| {
-                let resp = ui.text_edit_singleline(&mut self.query);
+                let resp = ui.add(TextEdit::singleline(&mut self.query).lock_focus(true));
                 if self.just_spawned {
                     resp.request_focus();
                     self.just_spawned = false;
                 }
-                let update_open = resp.changed();
+                let query_changed = resp.changed();
+                let searching = !self.query.trim().is_empty();
+                if searching {
+                    ui.checkbox(&mut settings.flat_results, "Flat list");
+                }
 
-                let mut query_submit = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                let query_submit = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
 
                 let max_height = ui.input(|i| i.screen_rect.height() * 0.5);
                 let scroll_area_width = resp.rect.width() - 30.0;
 
+                // Templates matching the query, best matches first. Templates
+                // only matching on keywords come after the ones matching on
+                // their label, and don't highlight anything in the label.
+                // Templates matching equally well keep their order.
                 let all_kinds = all_kinds.all_kinds();
-                let mut categories: BTreeMap<String, Vec<&NodeTemplate>> = Default::default();
-                let mut orphan_kinds = Vec::new();
+                let mut matches: Vec<(bool, (&NodeTemplate, String, FuzzyMatch))> = all_kinds
+                    .iter()
+                    .filter_map(|kind| {
+                        let kind_name = kind.node_finder_label(user_state).to_string();
+                        if let Some(found) = fuzzy_match(&self.query, &kind_name) {
+                            return Some((false, (kind, kind_name, found)));
+                        }
+                        let score = kind
+                            .node_finder_keywords(user_state)
+                            .iter()
+                            .filter_map(|keyword| fuzzy_match(&self.query, keyword))
+                            .map(|found| found.score)
+                            .max()?;
+                        let found = FuzzyMatch {
+                            score,
+                            matched: Vec::new(),
+                        };
+                        Some((true, (kind, kind_name, found)))
+                    })
+                    .collect();
+                matches.sort_by_key(|(by_keyword, (_, _, found))| {
+                    (*by_keyword, std::cmp::Reverse(found.score))
+                });
+                let results: Vec<(&NodeTemplate, String, FuzzyMatch)> =
+                    matches.into_iter().map(|(_, result)| result).collect();
 
-                for kind in &all_kinds {
+                let mut categories: BTreeMap<String, Vec<usize>> = Default::default();
+                let mut orphan_kinds = Vec::new();
+                for (index, (kind, _, _)) in results.iter().enumerate() {
                     let kind_categories = kind.node_finder_categories(user_state);
 
                     if kind_categories.is_empty() {
-                        orphan_kinds.push(kind);
+                        orphan_kinds.push(index);
                     } else {
                         for category in kind_categories {
-                            categories.entry(category.name()).or_default().push(kind);
+                            categories.entry(category.name()).or_default().push(index);
+                        }
+                    }
+                }
+                // While searching, the categories with the best matches come
+                // first.
+                let mut categories: Vec<(String, Vec<usize>)> = categories.into_iter().collect();
+                if searching {
+                    categories.sort_by_key(|(_, indices)| indices[0]);
+                }
+
+                // Searching expands the categories with results, and clearing
+                // the search collapses them all.
+                if query_changed {
+                    self.open_categories = if searching {
+                        categories.iter().map(|(name, _)| name.clone()).collect()
+                    } else {
+                        Default::default()
+                    };
+                }
+                // Favorites and recent templates, as indices in the results.
+                let shortcuts = |labels: &[String]| -> Vec<usize> {
+                    if searching {
+                        return Vec::new();
+                    }
+                    labels
+                        .iter()
+                        .filter_map(|label| results.iter().position(|(_, name, _)| name == label))
+                        .collect()
+                };
+                let sections = [
+                    (
+                        "Favorites",
+                        shortcuts(&settings.favorites)
+                            .into_iter()
+                            .map(FinderEntry::Favorite)
+                            .collect::<Vec<_>>(),
+                    ),
+                    (
+                        "Recent",
+                        shortcuts(&settings.recent)
+                            .into_iter()
+                            .map(FinderEntry::Recent)
+                            .collect(),
+                    ),
+                ];
+
+                let flat = searching && settings.flat_results;
+                let entries = |open_categories: &HashSet<String>| {
+                    let mut entries: Vec<FinderEntry> = sections
+                        .iter()
+                        .flat_map(|(_, section)| section.iter().copied())
+                        .collect();
+                    if flat {
+                        entries.extend((0..results.len()).map(|index| FinderEntry::Template {
+                            category: None,
+                            index,
+                        }));
+                        return entries;
+                    }
+                    for (category, (name, indices)) in categories.iter().enumerate() {
+                        entries.push(FinderEntry::Category(category));
+                        if open_categories.contains(name) {
+                            entries.extend(indices.iter().map(|index| FinderEntry::Template {
+                                category: Some(category),
+                                index: *index,
+                            }));
                         }
                     }
+                    entries.extend(orphan_kinds.iter().map(|index| FinderEntry::Template {
+                        category: None,
+                        index: *index,
+                    }));
+                    entries
+                };
+
+                // Keyboard navigation
+                let mut visible = entries(&self.open_categories);
+                if query_changed {
+                    // The best match is highlighted, so Enter creates it.
+                    self.current = visible
+                        .iter()
+                        .position(|entry| !matches!(entry, FinderEntry::Category(_)))
+                        .unwrap_or(0);
+                }
+                let last = visible.len().saturating_sub(1);
+                if down {
+                    self.current = (self.current + 1).min(last);
+                }
+                if up {
+                    self.current = self.current.saturating_sub(1);
+                }
+                if page_down {
+                    self.current = (self.current + FINDER_PAGE_SIZE).min(last);
+                }
+                if page_up {
+                    self.current = self.current.saturating_sub(FINDER_PAGE_SIZE);
+                }
+                self.current = self.current.min(last);
+                let mut toggle_category = |category: usize, open: Option<bool>| {
+                    let name = &categories[category].0;
+                    let open = open.unwrap_or(!self.open_categories.contains(name));
+                    if open {
+                        self.open_categories.insert(name.clone());
+                    } else {
+                        self.open_categories.remove(name);
+                    }
+                };
+                match visible.get(self.current).copied() {
+                    Some(FinderEntry::Category(category)) if tab || query_submit => {
+                        toggle_category(category, None);
+                        resp.request_focus();
+                    }
+                    Some(FinderEntry::Category(category)) if right || left => {
+                        toggle_category(category, Some(right));
+                    }
+                    // The left and right keys keep moving the text cursor
+                    // while a template is highlighted.
+                    Some(FinderEntry::Template {
+                        category: Some(category),
+                        ..
+                    }) if tab => {
+                        toggle_category(category, Some(false));
+                        self.current = visible
+                            .iter()
+                            .position(|entry| *entry == FinderEntry::Category(category))
+                            .unwrap_or(0);
+                    }
+                    Some(
+                        FinderEntry::Template { index, .. }
+                        | FinderEntry::Favorite(index)
+                        | FinderEntry::Recent(index),
+                    ) if query_submit => {
+                        submitted_archetype = Some(results[index].0.clone());
+                    }
+                    _ => {}
                 }
+                visible = entries(&self.open_categories);
+                let current = visible.get(self.current).copied();
+                let scroll_to_current = down || up || page_down || page_up || tab;
 
-                Frame::default()
-                    .inner_margin(vec2(10.0, 10.0))
-                    .show(ui, |ui| {
-                        ScrollArea::vertical()
-                            .max_height(max_height)
-                            .show(ui, |ui| {
-                                ui.set_width(scroll_area_width);
-                                for (category, kinds) in categories {
-                                    let filtered_kinds: Vec<_> = kinds
-                                        .into_iter()
-                                        .map(|kind| {
-                                            let kind_name =
-                                                kind.node_finder_label(user_state).to_string();
-                                            (kind, kind_name)
-                                        })
-                                        .filter(|(_kind, kind_name)| {
-                                            kind_name
-                                                .to_lowercase()
-                                                .contains(self.query.to_lowercase().as_str())
-                                        })
-                                        .collect();
-
-                                    if !filtered_kinds.is_empty() {
-                                        let default_open = !self.query.is_empty();
-
-                                        CollapsingHeader::new(&category)
-                                            .default_open(default_open)
-                                            .open(update_open.then_some(default_open))
+                ui.horizontal_top(|ui| {
+                    let mut hovered = None;
+                    let mut result_entry = |ui: &mut Ui, entry: FinderEntry| {
+                        let index = match entry {
+                            FinderEntry::Category(_) => return,
+                            FinderEntry::Template { index, .. }
+                            | FinderEntry::Favorite(index)
+                            | FinderEntry::Recent(index) => index,
+                        };
+                        let (kind, kind_name, found) = &results[index];
+                        ui.horizontal(|ui| {
+                            let favorite = settings.is_favorite(kind_name);
+                            let (star, hover_text) = if favorite {
+                                ("★", "Unpin from favorites")
+                            } else {
+                                ("☆", "Pin to favorites")
+                            };
+                            let star = ui.add(Button::new(star).frame(false).small());
+                            if star.on_hover_text(hover_text).clicked() {
+                                toggled_favorite = Some(kind_name.clone());
+                            }
+                            if let Some(icon) = kind.node_finder_icon(user_state) {
+                                ui.label(icon);
+                            }
+
+                            let label = highlighted_label(
+                                ui,
+                                kind_name,
+                                &found.matched,
+                                style.finder_highlight,
+                            );
+                            let is_current = current == Some(entry);
+                            let response = ui.selectable_label(is_current, label);
+                            if is_current && scroll_to_current {
+                                response.scroll_to_me(None);
+                            }
+                            if response.hovered() {
+                                hovered = Some(index);
+                            }
+                            if response.clicked() {
+                                submitted_archetype = Some((*kind).clone());
+                            }
+                        });
+                    };
+
+                    Frame::default()
+                        .inner_margin(vec2(10.0, 10.0))
+                        .show(ui, |ui| {
+                            ScrollArea::vertical()
+                                .max_height(max_height)
+                                .show(ui, |ui| {
+                                    ui.set_width(scroll_area_width);
+                                    for (heading, section) in &sections {
+                                        if section.is_empty() {
+                                            continue;
+                                        }
+                                        ui.label(RichText::new(*heading).strong());
+                                        for entry in section {
+                                            result_entry(ui, *entry);
+                                        }
+                                        ui.separator();
+                                    }
+
+                                    if flat {
+                                        for entry in visible {
+                                            result_entry(ui, entry);
+                                        }
+                                        return;
+                                    }
+
+                                    for (category, (name, indices)) in categories.iter().enumerate()
+                                    {
+                                        let collapsing = CollapsingHeader::new(name)
+                                            .open(Some(self.open_categories.contains(name)))
                                             .show(ui, |ui| {
-                                                for (kind, kind_name) in filtered_kinds {
-                                                    if ui
-                                                        .selectable_label(false, kind_name)
-                                                        .clicked()
-                                                    {
-                                                        submitted_archetype = Some(kind.clone());
-                                                    } else if query_submit {
-                                                        submitted_archetype = Some(kind.clone());
-                                                        query_submit = false;
-                                                    }
+                                                for index in indices {
+                                                    result_entry(
+                                                        ui,
+                                                        FinderEntry::Template {
+                                                            category: Some(category),
+                                                            index: *index,
+                                                        },
+                                                    );
                                                 }
                                             });
+                                        let header = collapsing.header_response;
+                                        if header.clicked() && !self.open_categories.remove(name) {
+                                            self.open_categories.insert(name.clone());
+                                        }
+                                        if current == Some(FinderEntry::Category(category)) {
+                                            ui.painter().rect_stroke(
+                                                header.rect,
+                                                2.0,
+                                                ui.visuals().selection.stroke,
+                                            );
+                                            if scroll_to_current {
+                                                header.scroll_to_me(None);
+                                            }
+                                        }
                                     }
-                                }
-
-                                for kind in orphan_kinds {
-                                    let kind_name = kind.node_finder_label(user_state).to_string();
 
-                                    if ui.selectable_label(false, kind_name).clicked() {
-                                        submitted_archetype = Some(kind.clone());
-                                    } else if query_submit {
-                                        submitted_archetype = Some(kind.clone());
-                                        query_submit = false;
+                                    for index in orphan_kinds {
+                                        result_entry(
+                                            ui,
+                                            FinderEntry::Template {
+                                                category: None,
+                                                index,
+                                            },
+                                        );
                                     }
-                                }
-                            });
+                                });
+                        });
+
+                    let preview = hovered.or(match current {
+                        Some(
+                            FinderEntry::Template { index, .. }
+                            | FinderEntry::Favorite(index)
+                            | FinderEntry::Recent(index),
+                        ) => Some(index),
+                        _ => None,
                     });
+                    if let Some(index) = preview {
+                        ui.separator();
+                        let kind = results[index].0;
+                        let ports = template_ports.get(kind, user_state);
+                        template_preview(ui, kind, ports, style, user_state);
+                    }
+                });
             });
         });
 
+        if let Some(label) = toggled_favorite {
+            settings.toggle_favorite(&label);
+        }
+        if let Some(kind) = &submitted_archetype {
+            settings.record_use(&kind.node_finder_label(user_state));
+        }
         submitted_archetype
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_fuzzy_match() {
+        let found = fuzzy_match("gblr", "Gaussian Blur").unwrap();
+        assert_eq!(found.matched, vec![0, 9, 10, 12]);
+        assert!(fuzzy_match("blur gaus", "Gaussian Blur").is_some());
+        assert!(fuzzy_match("gaus blurry", "Gaussian Blur").is_none());
+        assert!(fuzzy_match("xyz", "Gaussian Blur").is_none());
+        assert_eq!(fuzzy_match("", "Anything"), Some(FuzzyMatch::default()));
+
+        // Word starts and consecutive characters rank higher
+        let score = |query, text| fuzzy_match(query, text).unwrap().score;
+        assert!(score("blur", "Blur") > score("blur", "Gaussian Blur"));
+        assert!(score("mn", "Make Number") > score("mn", "Mean"));
+        assert!(score("vec", "Vector") > score("vec", "Vertex Count"));
+    }
+
+    #[test]
+    pub fn test_recent_and_favorites() {
+        let mut settings = NodeFinderSettings::default();
+        for label in ["Add", "Blur", "Add"] {
+            settings.record_use(label);
+        }
+        assert_eq!(settings.recent, ["Add", "Blur"]);
+        for index in 0..MAX_RECENT + 1 {
+            settings.record_use(&index.to_string());
+        }
+        assert_eq!(settings.recent.len(), MAX_RECENT);
+        assert_eq!(settings.recent[0], MAX_RECENT.to_string());
+
+        settings.toggle_favorite("Blur");
+        settings.toggle_favorite("Add");
+        assert_eq!(settings.favorites, ["Blur", "Add"]);
+        settings.toggle_favorite("Blur");
+        assert!(!settings.is_favorite("Blur"));
+        assert_eq!(settings.favorites, ["Add"]);
+    }
+}
@@ -0,0 +1,307 @@
+use super::*;
+use egui::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Radius of the handles of reroute points, at zoom 1.0.
+const REROUTE_RADIUS: f32 = 6.0;
+/// How close to a wire, at zoom 1.0, a click inserts a reroute point.
+const DISTANCE_TO_WIRE: f32 = 8.0;
+
+/// The reroute points of a connection. The wire goes through these points,
+/// in order, on its way from the output to the input.
+///
+/// Reroutes only change how the wire is drawn: the connection stored in the
+/// [`Graph`] is the same, so [`Graph::connection`] still returns the real
+/// upstream output and evaluation code can ignore reroutes entirely.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct WireReroutes {
+    pub output: OutputId,
+    pub input: InputId,
+    /// The reroute points, in graph space, from the output to the input.
+    pub points: Vec<egui::Pos2>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Returns the reroute points of the connection from `output` to `input`.
+    pub fn reroute_points(&self, output: OutputId, input: InputId) -> &[egui::Pos2] {
+        self.reroutes
+            .iter()
+            .find(|wire| wire.output == output && wire.input == input)
+            .map_or(&[], |wire| &wire.points)
+    }
+
+    /// Inserts a reroute point at `position`, in graph space, so that it
+    /// becomes the `index`-th point of the connection from `output` to
+    /// `input`.
+    pub fn insert_reroute(
+        &mut self,
+        output: OutputId,
+        input: InputId,
+        index: usize,
+        position: egui::Pos2,
+    ) {
+        let wire = match self
+            .reroutes
+            .iter()
+            .position(|wire| wire.output == output && wire.input == input)
+        {
+            Some(existing) => &mut self.reroutes[existing],
+            None => {
+                self.reroutes.push(WireReroutes {
+                    output,
+                    input,
+                    points: Vec::new(),
+                });
+                self.reroutes.last_mut().unwrap()
+            }
+        };
+        let index = index.min(wire.points.len());
+        wire.points.insert(index, position);
+    }
+
+    /// Removes the `index`-th reroute point of the connection from `output`
+    /// to `input`, if it exists.
+    pub fn remove_reroute(&mut self, output: OutputId, input: InputId, index: usize) {
+        for wire in &mut self.reroutes {
+            if wire.output == output && wire.input == input && index < wire.points.len() {
+                wire.points.remove(index);
+            }
+        }
+        self.reroutes.retain(|wire| !wire.points.is_empty());
+    }
+
+    /// Removes the reroutes of connections that no longer exist in the graph.
+    pub fn prune_reroutes(&mut self) {
+        let graph = &self.graph;
+        self.reroutes.retain(|wire| {
+            graph.inputs.contains_key(wire.input)
+                && graph.connections(wire.input).contains(&wire.output)
+        });
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: DataTypeTrait<UserState> + Clone,
+    ValueType: Clone,
+{
+    /// Draws the connections, through their reroute points. Clicking a wire on
+    /// the `background` selects it, and double-clicking or alt-clicking it
+    /// inserts a reroute point. Reroute points are removed the same way. Wires
+    /// are not hovered while `dragging_wire`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn draw_wires(
+        &mut self,
+        ui: &mut Ui,
+        background: &Response,
+        editor_rect: Rect,
+        cursor_pos: Pos2,
+        style: &GraphStyle,
+        node_views: &SecondaryMap<NodeId, NodeView>,
+        port_locations: &PortLocations,
+        dragging_wire: bool,
+        user_state: &mut UserState,
+    ) {
+        let zoom = self.pan_zoom.zoom;
+        self.prune_reroutes();
+        if self.selected_connection.is_some_and(|(output, input)| {
+            !self.graph.inputs.contains_key(input)
+                || !self.graph.connections(input).contains(&output)
+        }) {
+            self.selected_connection = None;
+        }
+        let alt_down = ui.input(|i| i.modifiers.alt);
+        let mut reroute_actions = Vec::new();
+
+        // Wires are laid out before being painted, to find the one under the
+        // cursor first.
+        let mut wires: Vec<(OutputId, InputId, Wire)> = Vec::new();
+        let mut nearest_wire: Option<(f32, OutputId, InputId, usize)> = None;
+        for (input, outputs) in self.graph.connections.iter() {
+            // Ports of hidden nodes have no location. Connections inside a
+            // collapsed group are not drawn either.
+            let Some(input_pos) = port_locations.get(&AnyParameterId::Input(input)).copied() else {
+                continue;
+            };
+            let input_view = node_views.get(self.graph[input].node);
+            for (index, output) in outputs.iter().copied().enumerate() {
+                let Some(src_pos) = port_locations.get(&AnyParameterId::Output(output)).copied()
+                else {
+                    continue;
+                };
+                let output_view = node_views.get(self.graph[output].node);
+                if matches!(input_view, Some(NodeView::Collapsed(_))) && input_view == output_view {
+                    continue;
+                }
+                let port_type = self
+                    .graph
+                    .any_param_type(AnyParameterId::Output(output))
+                    .unwrap();
+                let dst_pos = fanned_wire_pos(input_pos, index, outputs.len(), zoom);
+
+                let points: Vec<Pos2> = std::iter::once(src_pos)
+                    .chain(
+                        self.reroute_points(output, input)
+                            .iter()
+                            .map(|pos| self.pan_zoom.graph_to_screen(editor_rect.min, *pos)),
+                    )
+                    .chain(std::iter::once(dst_pos))
+                    .collect();
+                let mut path: Vec<Pos2> = Vec::new();
+                for (segment, ends) in points.windows(2).enumerate() {
+                    let segment_path = wire_path(self.wire_style, ends[0], ends[1], zoom);
+                    let distance = distance_to_path(cursor_pos, &segment_path);
+                    if !nearest_wire.is_some_and(|(nearest, ..)| nearest <= distance) {
+                        nearest_wire = Some((distance, output, input, segment));
+                    }
+                    let skip = usize::from(!path.is_empty());
+                    path.extend(segment_path.into_iter().skip(skip));
+                }
+                let wire = Wire {
+                    points,
+                    path,
+                    stroke: port_type.wire_stroke(Some((output, input)), style, user_state),
+                    highlight: WireHighlight::None,
+                    selection_color: style.wire_selection,
+                    zoom,
+                };
+                wires.push((output, input, wire));
+            }
+        }
+        let hovered_wire = nearest_wire.filter(|(distance, ..)| {
+            *distance < DISTANCE_TO_WIRE * zoom && background.hovered() && !dragging_wire
+        });
+        if let Some((_, output, input, segment)) = hovered_wire {
+            if background.double_clicked() || (background.clicked() && alt_down) {
+                let position = self.pan_zoom.screen_to_graph(editor_rect.min, cursor_pos);
+                reroute_actions.push(RerouteAction::Insert(output, input, segment, position));
+            }
+        }
+        if background.clicked() && !alt_down {
+            self.selected_connection = hovered_wire.map(|(_, output, input, _)| (output, input));
+        }
+
+        for (output, input, mut wire) in wires {
+            wire.highlight = if self.selected_connection == Some((output, input)) {
+                WireHighlight::Selected
+            } else if hovered_wire.is_some_and(|(_, o, i, _)| (o, i) == (output, input)) {
+                WireHighlight::Hovered
+            } else {
+                WireHighlight::None
+            };
+            let port_type = self
+                .graph
+                .any_param_type(AnyParameterId::Output(output))
+                .unwrap();
+            port_type.paint_wire(ui.painter(), &wire, user_state);
+
+            let reroutes = &wire.points[1..wire.points.len() - 1];
+            for (point, pos) in reroutes.iter().enumerate() {
+                let handle = ui.interact(
+                    Rect::from_center_size(*pos, Vec2::splat(2.0 * REROUTE_RADIUS * zoom)),
+                    Id::new((input, output, point, "reroute")),
+                    Sense::click_and_drag(),
+                );
+                let radius = if handle.hovered() || handle.dragged() {
+                    REROUTE_RADIUS * zoom
+                } else {
+                    0.7 * REROUTE_RADIUS * zoom
+                };
+                ui.painter().circle_filled(*pos, radius, wire.stroke.color);
+
+                if handle.double_clicked() || (handle.clicked() && alt_down) {
+                    reroute_actions.push(RerouteAction::Remove(output, input, point));
+                } else if handle.dragged() {
+                    let drag_delta = handle.drag_delta() / zoom;
+                    reroute_actions.push(RerouteAction::Move(output, input, point, drag_delta));
+                }
+            }
+        }
+
+        for action in reroute_actions {
+            match action {
+                RerouteAction::Insert(output, input, index, position) => {
+                    self.record_undo_step(None);
+                    self.insert_reroute(output, input, index, position);
+                }
+                RerouteAction::Move(output, input, index, drag_delta) => {
+                    self.record_undo_step(Some(UndoGroup::MoveNodes));
+                    if let Some(wire) = self
+                        .reroutes
+                        .iter_mut()
+                        .find(|wire| wire.output == output && wire.input == input)
+                    {
+                        wire.points[index] += drag_delta;
+                    }
+                }
+                RerouteAction::Remove(output, input, index) => {
+                    self.record_undo_step(None);
+                    self.remove_reroute(output, input, index);
+                }
+            }
+        }
+    }
+}
+
+/// Changes to reroute points requested by the user. Indices refer to the
+/// reroute points of the connection from the output to the input.
+enum RerouteAction {
+    Insert(OutputId, InputId, usize, Pos2),
+    Move(OutputId, InputId, usize, Vec2),
+    Remove(OutputId, InputId, usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_reroutes() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let a = state.graph.add_node(String::new(), (), |graph, node_id| {
+            graph.add_output_param(node_id, "out".into(), ());
+        });
+        let b = state.graph.add_node(String::new(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                (),
+                (),
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+        });
+        let output = state.graph[a].get_output("out").unwrap();
+        let input = state.graph[b].get_input("in").unwrap();
+        state.graph.add_connection(output, input);
+
+        state.insert_reroute(output, input, 0, egui::pos2(2.0, 0.0));
+        state.insert_reroute(output, input, 0, egui::pos2(1.0, 0.0));
+        state.insert_reroute(output, input, 5, egui::pos2(3.0, 0.0));
+        assert_eq!(
+            state.reroute_points(output, input),
+            &[
+                egui::pos2(1.0, 0.0),
+                egui::pos2(2.0, 0.0),
+                egui::pos2(3.0, 0.0)
+            ]
+        );
+
+        state.remove_reroute(output, input, 1);
+        assert_eq!(state.reroute_points(output, input).len(), 2);
+
+        // Reroutes don't affect the connections of the graph
+        assert_eq!(state.graph.connection(input), Some(output));
+
+        state.graph.remove_connection(input);
+        state.prune_reroutes();
+        assert!(state.reroutes.is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::color_hex_utils::*;
+use crate::utils::ColorUtils;
+use egui::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The colors and measurements used to draw the editor. Sizes are given at
+/// zoom 1.0. See [`crate::GraphEditorState::style`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct GraphStyle {
+    /// Fill color of the body of nodes.
+    pub node_background: Color32,
+    /// Color of the text in nodes.
+    pub node_text: Color32,
+    /// Fill color of the title bar of nodes, unless overridden by
+    /// [`crate::NodeDataTrait::titlebar_color`].
+    pub node_titlebar: Color32,
+    /// Outline drawn around unselected nodes.
+    pub node_outline: Stroke,
+    /// Outline drawn around selected nodes.
+    pub node_selection: Stroke,
+    /// Space between the border of nodes and their contents.
+    pub node_margin: Vec2,
+    pub node_rounding: f32,
+    /// Text style of node titles.
+    pub title_text_style: TextStyle,
+    /// Colors of the delete button of nodes, when idle, hovered and pressed.
+    pub close_button: Color32,
+    pub close_button_hovered: Color32,
+    pub close_button_pressed: Color32,
+    pub port_radius: f32,
+    /// Color of the ports under the cursor that can be connected to.
+    pub port_hovered: Color32,
+    /// Width of wires, used by the default
+    /// [`crate::DataTypeTrait::wire_stroke`].
+    pub wire_width: f32,
+    /// Color of the outline of the selected wire.
+    pub wire_selection: Color32,
+    pub finder_background: Color32,
+    pub finder_text: Color32,
+    /// Color of the characters of search results matching the query.
+    pub finder_highlight: Color32,
+    /// Fill color of the rectangle drawn during box selection.
+    pub box_selection_fill: Color32,
+    pub box_selection_stroke: Stroke,
+}
+
+impl GraphStyle {
+    /// The style used with dark egui visuals.
+    pub fn dark() -> Self {
+        let node_background = color_from_hex("#3f3f3f").unwrap();
+        Self {
+            node_background,
+            node_text: color_from_hex("#fefefe").unwrap(),
+            node_titlebar: node_background.lighten(0.8),
+            node_outline: Stroke::NONE,
+            node_selection: Stroke::new(1.0, Color32::WHITE.lighten(0.8)),
+            node_margin: vec2(15.0, 5.0),
+            node_rounding: 4.0,
+            title_text_style: TextStyle::Button,
+            close_button: color_from_hex("#aaaaaa").unwrap(),
+            close_button_hovered: color_from_hex("#dddddd").unwrap(),
+            close_button_pressed: color_from_hex("#ffffff").unwrap(),
+            port_radius: 5.0,
+            port_hovered: Color32::WHITE,
+            wire_width: 5.0,
+            wire_selection: Visuals::dark().selection.stroke.color,
+            finder_background: color_from_hex("#3f3f3f").unwrap(),
+            finder_text: color_from_hex("#fefefe").unwrap(),
+            finder_highlight: color_from_hex("#ffb454").unwrap(),
+            box_selection_fill: Color32::from_rgba_unmultiplied(200, 200, 200, 20),
+            box_selection_stroke: Stroke::new(
+                3.0,
+                Color32::from_rgba_unmultiplied(200, 200, 200, 180),
+            ),
+        }
+    }
+
+    /// The style used with light egui visuals.
+    pub fn light() -> Self {
+        let node_background = color_from_hex("#ffffff").unwrap();
+        Self {
+            node_background,
+            node_text: color_from_hex("#505050").unwrap(),
+            node_titlebar: node_background.lighten(0.8),
+            close_button: color_from_hex("#555555").unwrap(),
+            close_button_hovered: color_from_hex("#222222").unwrap(),
+            close_button_pressed: color_from_hex("#000000").unwrap(),
+            wire_selection: Visuals::light().selection.stroke.color,
+            finder_background: color_from_hex("#fefefe").unwrap(),
+            finder_text: color_from_hex("#3f3f3f").unwrap(),
+            finder_highlight: color_from_hex("#c05a00").unwrap(),
+            ..Self::dark()
+        }
+    }
+
+    /// Returns the preset matching the dark or light mode of `visuals`.
+    pub fn from_visuals(visuals: &Visuals) -> Self {
+        if visuals.dark_mode {
+            Self::dark()
+        } else {
+            Self::light()
+        }
+    }
+}
+
+impl Default for GraphStyle {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_style_presets() {
+        assert_eq!(
+            GraphStyle::from_visuals(&Visuals::dark()),
+            GraphStyle::dark()
+        );
+        assert_eq!(
+            GraphStyle::from_visuals(&Visuals::light()),
+            GraphStyle::light()
+        );
+        assert_ne!(GraphStyle::dark().node_text, GraphStyle::light().node_text);
+    }
+}
@@ -84,7 +84,112 @@ pub trait DataTypeTrait<UserState>: PartialEq + Eq {
     ///     }
     /// }
     /// ```
-    fn name(&self) -> std::borrow::Cow<str>;
+    fn name(&self) -> std::borrow::Cow<'_, str>;
+
+    /// Decides whether an output of this data type can be connected to an
+    /// input of type `input_type`. This is used when snapping and
+    /// highlighting ports while dragging a connection, and to decide whether
+    /// a connection is made when the drag ends.
+    ///
+    /// Override this to allow implicit conversions, wildcard types or subtype
+    /// relationships. The default implementation only allows connecting
+    /// equal types.
+    ///
+    /// ## Example
+    /// ```ignore
+    /// fn connection_compatibility(
+    ///     &self,
+    ///     input_type: &Self,
+    ///     _user_state: &mut MyUserState,
+    /// ) -> ConnectionCompatibility {
+    ///     match (self, input_type) {
+    ///         (_, MyDataType::Any) => ConnectionCompatibility::Compatible,
+    ///         (MyDataType::Scalar, MyDataType::Vec2) => {
+    ///             ConnectionCompatibility::Conversion("Scalar to Vec2".into())
+    ///         }
+    ///         (a, b) if a == b => ConnectionCompatibility::Compatible,
+    ///         _ => ConnectionCompatibility::Incompatible,
+    ///     }
+    /// }
+    /// ```
+    fn connection_compatibility(
+        &self,
+        input_type: &Self,
+        _user_state: &mut UserState,
+    ) -> ConnectionCompatibility {
+        if self == input_type {
+            ConnectionCompatibility::Compatible
+        } else {
+            ConnectionCompatibility::Incompatible
+        }
+    }
+
+    /// The stroke of the wires leaving outputs of this data type. The
+    /// `connection` is the output and input joined by the wire, or `None` for
+    /// the wire being dragged by the user. The default implementation draws
+    /// solid wires of [`GraphStyle::wire_width`], in the color returned by
+    /// [`Self::data_type_color`].
+    ///
+    /// ## Example
+    /// ```ignore
+    /// fn wire_stroke(
+    ///     &self,
+    ///     connection: Option<(OutputId, InputId)>,
+    ///     style: &GraphStyle,
+    ///     user_state: &mut MyUserState,
+    /// ) -> WireStroke {
+    ///     WireStroke {
+    ///         width: style.wire_width,
+    ///         dashed: connection.is_some_and(|(_, input)| user_state.is_optional(input)),
+    ///         ..WireStroke::new(self.data_type_color(user_state))
+    ///     }
+    /// }
+    /// ```
+    fn wire_stroke(
+        &self,
+        _connection: Option<(OutputId, InputId)>,
+        style: &GraphStyle,
+        user_state: &mut UserState,
+    ) -> WireStroke {
+        WireStroke {
+            width: style.wire_width,
+            ..WireStroke::new(self.data_type_color(user_state))
+        }
+    }
+
+    /// Paints a wire leaving an output of this data type. Override this to
+    /// draw custom wires. The default implementation calls [`Wire::paint`].
+    fn paint_wire(&self, painter: &egui::Painter, wire: &Wire, _user_state: &mut UserState) {
+        wire.paint(painter);
+    }
+}
+
+/// The result of [`DataTypeTrait::connection_compatibility`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionCompatibility {
+    /// The types can be connected directly.
+    Compatible,
+    /// The types can be connected through an implicit conversion. The string
+    /// describes the conversion, and is shown to the user while hovering the
+    /// port.
+    Conversion(std::borrow::Cow<'static, str>),
+    /// The types cannot be connected.
+    Incompatible,
+}
+
+impl ConnectionCompatibility {
+    /// Returns true if a connection is allowed, possibly with a conversion.
+    pub fn is_compatible(&self) -> bool {
+        !matches!(self, ConnectionCompatibility::Incompatible)
+    }
+
+    /// The description of the conversion, if one is needed.
+    pub fn conversion(&self) -> Option<&str> {
+        match self {
+            ConnectionCompatibility::Conversion(name) => Some(name),
+            _ => None,
+        }
+    }
 }
 
 /// This trait must be implemented for the `NodeData` generic parameter of the
@@ -184,6 +289,49 @@ where
     ) -> bool {
         true
     }
+
+    /// The size bounds of the node. Nodes fit their contents within
+    /// [`NodeSizeConstraints::fit`] until the user resizes them by dragging
+    /// their bottom-right corner.
+    fn size_constraints(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> NodeSizeConstraints {
+        NodeSizeConstraints::default()
+    }
+}
+
+/// The result of [`NodeDataTrait::size_constraints`]. All sizes are in graph
+/// space, and include the margins of the node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeSizeConstraints {
+    /// Nodes are never drawn smaller than this.
+    pub min: egui::Vec2,
+    /// Nodes can't be resized past this. Their contents may still make them
+    /// larger.
+    pub max: egui::Vec2,
+    /// The space available to the contents of nodes that were never resized.
+    /// These nodes shrink to fit their contents.
+    pub fit: egui::Vec2,
+}
+
+impl Default for NodeSizeConstraints {
+    fn default() -> Self {
+        Self {
+            min: egui::Vec2::ZERO,
+            max: egui::Vec2::INFINITY,
+            fit: egui::vec2(200.0, 200.0),
+        }
+    }
+}
+
+impl NodeSizeConstraints {
+    /// Returns `size` clamped between [`Self::min`] and [`Self::max`].
+    pub fn clamp(&self, size: egui::Vec2) -> egui::Vec2 {
+        size.min(self.max).max(self.min)
+    }
 }
 
 /// This trait can be implemented by any user type. The trait tells the library
@@ -194,6 +342,13 @@ pub trait NodeTemplateIter {
     fn all_kinds(&self) -> Vec<Self::Item>;
 }
 
+impl<T: Clone> NodeTemplateIter for Vec<T> {
+    type Item = T;
+    fn all_kinds(&self) -> Vec<T> {
+        self.clone()
+    }
+}
+
 /// Describes a category of nodes.
 ///
 /// Used by [`NodeTemplateTrait::node_finder_categories`] to categorize nodes
@@ -214,7 +369,7 @@ impl CategoryTrait for () {
     }
 }
 
-impl<'a> CategoryTrait for &'a str {
+impl CategoryTrait for &str {
     fn name(&self) -> String {
         self.to_string()
     }
@@ -251,7 +406,7 @@ pub trait NodeTemplateTrait: Clone {
     /// The return type is Cow<str> to allow returning owned or borrowed values
     /// more flexibly. Refer to the documentation for `DataTypeTrait::name` for
     /// more information
-    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<str>;
+    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<'_, str>;
 
     /// Vec of categories to which the node belongs.
     ///
@@ -262,6 +417,33 @@ pub trait NodeTemplateTrait: Clone {
         Vec::default()
     }
 
+    /// A longer explanation of what the node does, shown by the node finder
+    /// next to the search results.
+    fn node_finder_description(
+        &self,
+        _user_state: &mut Self::UserState,
+    ) -> Option<std::borrow::Cow<'_, str>> {
+        None
+    }
+
+    /// Other names the node finder matches this template by, like synonyms or
+    /// the names of similar nodes in other applications.
+    fn node_finder_keywords(
+        &self,
+        _user_state: &mut Self::UserState,
+    ) -> Vec<std::borrow::Cow<'_, str>> {
+        Vec::default()
+    }
+
+    /// A short text shown before the label in the node finder, usually a
+    /// single emoji or icon font glyph.
+    fn node_finder_icon(
+        &self,
+        _user_state: &mut Self::UserState,
+    ) -> Option<std::borrow::Cow<'_, str>> {
+        None
+    }
+
     /// Returns a descriptive name for the node kind, used in the graph.
     fn node_graph_label(&self, user_state: &mut Self::UserState) -> String;
 
@@ -277,8 +459,151 @@ pub trait NodeTemplateTrait: Clone {
         user_state: &mut Self::UserState,
         node_id: NodeId,
     );
+
+    /// Returns the ports of the nodes built from this template. The node
+    /// finder uses them to only offer templates that can be connected to a
+    /// wire dropped on empty space.
+    ///
+    /// The default implementation builds a node in a scratch graph with
+    /// [`Self::build_node`]. Override this if building nodes is expensive or
+    /// has side effects on the user state.
+    fn template_ports(&self, user_state: &mut Self::UserState) -> TemplatePorts<Self::DataType> {
+        let mut graph = Graph::new();
+        let label = self.node_graph_label(user_state);
+        let user_data = self.user_data(user_state);
+        let node_id = graph.add_node(label, user_data, |graph, node_id| {
+            self.build_node(graph, user_state, node_id)
+        });
+        TemplatePorts::from_node(graph, node_id)
+    }
+}
+
+/// A port of the nodes built from a template. See
+/// [`NodeTemplateTrait::template_ports`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplatePort<DataType> {
+    pub name: String,
+    pub typ: DataType,
+}
+
+/// The ports of the nodes built from a template, in the order they appear on
+/// the node. Inputs of kind [`InputParamKind::ConstantOnly`] are left out,
+/// since they can't be connected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplatePorts<DataType> {
+    pub inputs: Vec<TemplatePort<DataType>>,
+    pub outputs: Vec<TemplatePort<DataType>>,
+}
+
+impl<DataType> TemplatePorts<DataType> {
+    /// Takes the ports of `node_id` out of `graph`.
+    pub fn from_node<NodeData, ValueType>(
+        mut graph: Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+    ) -> Self {
+        let node = graph.nodes.remove(node_id).expect("Node should exist");
+        let inputs = node
+            .inputs
+            .into_iter()
+            .filter_map(|(name, id)| {
+                let param = graph.inputs.remove(id)?;
+                (!matches!(param.kind, InputParamKind::ConstantOnly)).then_some(TemplatePort {
+                    name,
+                    typ: param.typ,
+                })
+            })
+            .collect();
+        let outputs = node
+            .outputs
+            .into_iter()
+            .filter_map(|(name, id)| {
+                let param = graph.outputs.remove(id)?;
+                Some(TemplatePort {
+                    name,
+                    typ: param.typ,
+                })
+            })
+            .collect();
+        Self { inputs, outputs }
+    }
+
+    /// Whether a wire dragged from a port of type `typ` can be connected to
+    /// one of these ports. `from_output` tells which side `typ` belongs to.
+    pub fn accepts<UserState>(
+        &self,
+        typ: &DataType,
+        from_output: bool,
+        user_state: &mut UserState,
+    ) -> bool
+    where
+        DataType: DataTypeTrait<UserState>,
+    {
+        if from_output {
+            self.inputs.iter().any(|port| {
+                typ.connection_compatibility(&port.typ, user_state)
+                    .is_compatible()
+            })
+        } else {
+            self.outputs.iter().any(|port| {
+                port.typ
+                    .connection_compatibility(typ, user_state)
+                    .is_compatible()
+            })
+        }
+    }
 }
 
 /// The custom user response types when drawing nodes in the graph must
 /// implement this trait.
 pub trait UserResponseTrait: Clone + std::fmt::Debug {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum TestType {
+        Scalar,
+        Vector,
+    }
+
+    impl DataTypeTrait<()> for TestType {
+        fn data_type_color(&self, _user_state: &mut ()) -> egui::Color32 {
+            egui::Color32::WHITE
+        }
+
+        fn name(&self) -> std::borrow::Cow<'_, str> {
+            std::borrow::Cow::Borrowed(match self {
+                TestType::Scalar => "scalar",
+                TestType::Vector => "vector",
+            })
+        }
+    }
+
+    #[test]
+    pub fn test_template_ports() {
+        let mut graph = Graph::<(), TestType, ()>::new();
+        let node_id = graph.add_node("Scale".into(), (), |graph, node_id| {
+            for (name, typ, kind) in [
+                ("v", TestType::Vector, InputParamKind::ConnectionOrConstant),
+                ("factor", TestType::Scalar, InputParamKind::ConstantOnly),
+            ] {
+                graph.add_input_param(node_id, name.into(), typ, (), kind, true);
+            }
+            graph.add_output_param(node_id, "out".into(), TestType::Vector);
+        });
+
+        let ports = TemplatePorts::from_node(graph, node_id);
+        let names = |ports: &[TemplatePort<TestType>]| {
+            ports
+                .iter()
+                .map(|port| port.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&ports.inputs), ["v"]);
+        assert_eq!(names(&ports.outputs), ["out"]);
+        assert!(ports.accepts(&TestType::Vector, true, &mut ()));
+        assert!(ports.accepts(&TestType::Vector, false, &mut ()));
+        assert!(!ports.accepts(&TestType::Scalar, true, &mut ()));
+    }
+}
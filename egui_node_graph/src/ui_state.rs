@@ -4,13 +4,37 @@ use std::marker::PhantomData;
 #[cfg(feature = "persistence")]
 use serde::{Deserialize, Serialize};
 
-#[derive(Default, Copy, Clone)]
+/// The default lower bound for [`GraphEditorState::min_zoom`].
+pub const DEFAULT_MIN_ZOOM: f32 = 0.2;
+/// The default upper bound for [`GraphEditorState::max_zoom`].
+pub const DEFAULT_MAX_ZOOM: f32 = 3.0;
+
+#[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
 pub struct PanZoom {
+    /// Offset of the graph origin, in screen units, relative to the top-left
+    /// corner of the editor.
     pub pan: egui::Vec2,
+    /// Scale factor applied to the graph. A zoom of 1.0 draws nodes at their
+    /// natural size.
     pub zoom: f32,
 }
 
+#[cfg(feature = "persistence")]
+fn default_min_zoom() -> f32 {
+    DEFAULT_MIN_ZOOM
+}
+
+#[cfg(feature = "persistence")]
+fn default_max_zoom() -> f32 {
+    DEFAULT_MAX_ZOOM
+}
+
+#[cfg(feature = "persistence")]
+fn default_animate_view() -> bool {
+    true
+}
+
 #[derive(Clone)]
 #[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
 pub struct GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState> {
@@ -24,14 +48,106 @@ pub struct GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserSta
     /// The currently selected node. Some interface actions depend on the
     /// currently selected node.
     pub selected_nodes: Vec<NodeId>,
+    /// The currently selected comment frames.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub selected_frames: Vec<FrameId>,
+    /// The currently selected sticky notes.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub selected_notes: Vec<NoteId>,
+    /// The connection clicked by the user, if any.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub selected_connection: Option<(OutputId, InputId)>,
     /// The mouse drag start position for an ongoing box selection.
     pub ongoing_box_selection: Option<egui::Pos2>,
+    /// The selection the ongoing box selection is combined with, when it was
+    /// started while holding shift or ctrl.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub box_selection_base: Option<BoxSelectionBase>,
     /// The position of each node.
     pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
+    /// The size chosen by the user for the nodes they resized, in graph
+    /// space. Nodes without a size fit their contents.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub custom_node_sizes: SecondaryMap<NodeId, egui::Vec2>,
+    /// The size of each node, in graph space, measured when it was last drawn.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub node_sizes: SecondaryMap<NodeId, egui::Vec2>,
+    /// The nodes collapsed to their title bar. See
+    /// [`Self::set_node_collapsed`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub collapsed_nodes: SecondaryMap<NodeId, ()>,
     /// The node finder is used to create new nodes.
     pub node_finder: Option<NodeFinder<NodeTemplate>>,
-    /// The panning of the graph viewport.
+    /// Options, favorites and recently used templates of the node finder,
+    /// kept while it is closed.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub node_finder_settings: NodeFinderSettings,
+    /// The ports of the templates offered by the node finder, built when they
+    /// are first needed.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub template_ports: TemplatePortsCache<DataType>,
+    /// Groups of nodes, drawn as a single node when collapsed.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub groups: SlotMap<GroupId, NodeGroup>,
+    /// The group the user has entered, if any. Only the nodes inside it are
+    /// shown.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub open_group: Option<GroupId>,
+    /// Titled rectangles drawn behind the nodes.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub frames: SlotMap<FrameId, CommentFrame>,
+    /// Free-floating text notes.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub notes: SlotMap<NoteId, StickyNote>,
+    /// Points that connection wires are routed through.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub reroutes: Vec<WireReroutes>,
+    /// Settings of the automatic layout of nodes.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub layout: LayoutSettings,
+    /// The shape of the wires drawn for connections.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub wire_style: WireStyle,
+    /// The background grid, and whether nodes snap to it.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub grid: Grid,
+    /// When set, a minimap of the graph is drawn in a corner of the editor.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub minimap: Option<Minimap>,
+    /// The colors and measurements used to draw the editor. When unset, the
+    /// [`GraphStyle::dark`] or [`GraphStyle::light`] preset is used to match
+    /// the egui visuals.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub style: Option<GraphStyle>,
+    /// The panning and zoom of the graph viewport.
     pub pan_zoom: PanZoom,
+    /// The minimum zoom level reachable with ctrl+scroll or pinch gestures.
+    #[cfg_attr(feature = "persistence", serde(default = "default_min_zoom"))]
+    pub min_zoom: f32,
+    /// The maximum zoom level reachable with ctrl+scroll or pinch gestures.
+    #[cfg_attr(feature = "persistence", serde(default = "default_max_zoom"))]
+    pub max_zoom: f32,
+    /// Animates the view changes requested with [`Self::frame_all`],
+    /// [`Self::frame_selection`] and [`Self::focus_node`].
+    #[cfg_attr(feature = "persistence", serde(default = "default_animate_view"))]
+    pub animate_view: bool,
+    /// A view change to apply the next time the editor is drawn.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub view_request: Option<ViewRequest>,
+    /// The ongoing animation of the view, if any.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub view_animation: Option<ViewAnimation>,
+    /// When set, connections that would introduce a cycle in the graph are
+    /// rejected while the user drags a wire.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub reject_cycles: bool,
+    /// The undo / redo history of the editor. It is not persisted.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub undo_history: UndoHistory<NodeData, DataType, ValueType>,
+    /// The nodes copied with ctrl+C or ctrl+X. With the `persistence` feature,
+    /// they are also stored in the system clipboard as text.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub clipboard: Option<GraphClipboard<NodeData, DataType, ValueType>>,
     pub _user_state: PhantomData<fn() -> UserState>,
 }
 
@@ -47,6 +163,22 @@ impl<NodeData, DataType, ValueType, NodeKind, UserState>
             ..Default::default()
         }
     }
+
+    /// Returns whether `node` is collapsed to its title bar.
+    pub fn is_node_collapsed(&self, node: NodeId) -> bool {
+        self.collapsed_nodes.contains_key(node)
+    }
+
+    /// Collapses `node` to its title bar, or expands it back. While collapsed,
+    /// the connected inputs and outputs of the node are merged into a single
+    /// port on each side of the title bar.
+    pub fn set_node_collapsed(&mut self, node: NodeId, collapsed: bool) {
+        if collapsed {
+            self.collapsed_nodes.insert(node, ());
+        } else {
+            self.collapsed_nodes.remove(node);
+        }
+    }
 }
 impl<NodeData, DataType, ValueType, NodeKind, UserState> Default
     for GraphEditorState<NodeData, DataType, ValueType, NodeKind, UserState>
@@ -57,16 +189,56 @@ impl<NodeData, DataType, ValueType, NodeKind, UserState> Default
             node_order: Default::default(),
             connection_in_progress: Default::default(),
             selected_nodes: Default::default(),
+            selected_frames: Default::default(),
+            selected_notes: Default::default(),
+            selected_connection: None,
             ongoing_box_selection: Default::default(),
+            box_selection_base: None,
             node_positions: Default::default(),
+            custom_node_sizes: Default::default(),
+            node_sizes: Default::default(),
+            collapsed_nodes: Default::default(),
             node_finder: Default::default(),
+            node_finder_settings: Default::default(),
+            template_ports: Default::default(),
+            groups: Default::default(),
+            open_group: None,
+            frames: Default::default(),
+            notes: Default::default(),
+            reroutes: Default::default(),
+            layout: Default::default(),
+            wire_style: Default::default(),
+            grid: Default::default(),
+            minimap: None,
+            style: None,
             pan_zoom: Default::default(),
+            min_zoom: DEFAULT_MIN_ZOOM,
+            max_zoom: DEFAULT_MAX_ZOOM,
+            animate_view: true,
+            view_request: None,
+            view_animation: None,
+            reject_cycles: false,
+            undo_history: Default::default(),
+            clipboard: None,
             _user_state: Default::default(),
         }
     }
 }
 
+impl Default for PanZoom {
+    fn default() -> Self {
+        Self {
+            pan: egui::Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
 impl PanZoom {
+    /// Multiplies the current zoom by `zoom_delta`, clamping the result to
+    /// the `zoom_min..=zoom_max` range. The pan is adjusted so that `point`,
+    /// given relative to the top-left corner of the editor, stays fixed on
+    /// screen.
     pub fn adjust_zoom(
         &mut self,
         zoom_delta: f32,
@@ -74,10 +246,150 @@ impl PanZoom {
         zoom_min: f32,
         zoom_max: f32,
     ) {
-        let zoom_clamped = (self.zoom + zoom_delta).clamp(zoom_min, zoom_max);
-        let zoom_delta = zoom_clamped - self.zoom;
+        let zoom_clamped = (self.zoom * zoom_delta).clamp(zoom_min, zoom_max);
+        let zoom_ratio = zoom_clamped / self.zoom;
+
+        self.zoom = zoom_clamped;
+        self.pan = point - (point - self.pan) * zoom_ratio;
+    }
+
+    /// Converts a position in graph space (e.g. a value in
+    /// [`GraphEditorState::node_positions`]) to screen space. `origin` is the
+    /// top-left corner of the editor.
+    pub fn graph_to_screen(&self, origin: egui::Pos2, pos: egui::Pos2) -> egui::Pos2 {
+        origin + self.pan + pos.to_vec2() * self.zoom
+    }
+
+    /// Converts a position in screen space to graph space. `origin` is the
+    /// top-left corner of the editor.
+    pub fn screen_to_graph(&self, origin: egui::Pos2, pos: egui::Pos2) -> egui::Pos2 {
+        ((pos - origin - self.pan) / self.zoom).to_pos2()
+    }
+}
+
+/// How a click or a box selection is combined with the current selection.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// The clicked or boxed items replace the selection.
+    #[default]
+    Replace,
+    /// The items are added to the selection. Used while holding shift.
+    Add,
+    /// Clicked items are removed from the selection if they were selected,
+    /// and added otherwise. Used when clicking while holding ctrl.
+    Toggle,
+    /// The items are removed from the selection. Used when box selecting
+    /// while holding ctrl.
+    Subtract,
+}
+
+impl SelectionMode {
+    /// Returns the selection resulting from combining `items` with the
+    /// previous selection, `base`.
+    pub fn combine<T: Copy + PartialEq>(
+        self,
+        base: &[T],
+        items: impl IntoIterator<Item = T>,
+    ) -> Vec<T> {
+        let items: Vec<T> = items.into_iter().collect();
+        match self {
+            SelectionMode::Replace => items,
+            SelectionMode::Add => {
+                let mut selection = base.to_vec();
+                selection.extend(items.into_iter().filter(|item| !base.contains(item)));
+                selection
+            }
+            SelectionMode::Toggle => {
+                let mut selection: Vec<T> = base
+                    .iter()
+                    .copied()
+                    .filter(|item| !items.contains(item))
+                    .collect();
+                selection.extend(items.into_iter().filter(|item| !base.contains(item)));
+                selection
+            }
+            SelectionMode::Subtract => base
+                .iter()
+                .copied()
+                .filter(|item| !items.contains(item))
+                .collect(),
+        }
+    }
+}
+
+/// The selection when an ongoing box selection started, which the selected
+/// box is combined with.
+#[derive(Clone, Debug, Default)]
+pub struct BoxSelectionBase {
+    pub mode: SelectionMode,
+    pub nodes: Vec<NodeId>,
+    pub frames: Vec<FrameId>,
+    pub notes: Vec<NoteId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, vec2};
+
+    #[test]
+    pub fn test_zoom_around_point() {
+        let origin = pos2(100.0, 50.0);
+        let mut pan_zoom = PanZoom {
+            pan: vec2(30.0, -20.0),
+            zoom: 1.0,
+        };
+        let point = vec2(200.0, 120.0);
+        let under_point = pan_zoom.screen_to_graph(origin, origin + point);
+
+        pan_zoom.adjust_zoom(1.5, point, 0.5, 2.0);
+        assert_eq!(pan_zoom.zoom, 1.5);
+        let moved = pan_zoom.graph_to_screen(origin, under_point) - (origin + point);
+        assert!(moved.length() < 1e-4);
+
+        // The zoom is clamped, and the point still stays in place
+        pan_zoom.adjust_zoom(10.0, point, 0.5, 2.0);
+        assert_eq!(pan_zoom.zoom, 2.0);
+        let moved = pan_zoom.graph_to_screen(origin, under_point) - (origin + point);
+        assert!(moved.length() < 1e-4);
+    }
+
+    #[test]
+    pub fn test_screen_graph_round_trip() {
+        let origin = pos2(10.0, 20.0);
+        let pan_zoom = PanZoom {
+            pan: vec2(-35.0, 12.5),
+            zoom: 0.75,
+        };
+        let graph_pos = pos2(40.0, 80.0);
+        let screen_pos = pan_zoom.graph_to_screen(origin, graph_pos);
+        assert_eq!(screen_pos, pos2(10.0 - 35.0 + 30.0, 20.0 + 12.5 + 60.0));
+        let back = pan_zoom.screen_to_graph(origin, screen_pos);
+        assert!((back - graph_pos).length() < 1e-4);
+    }
+
+    #[test]
+    pub fn test_selection_modes() {
+        let base = [1, 2, 3];
+        assert_eq!(SelectionMode::Replace.combine(&base, [3, 4]), vec![3, 4]);
+        assert_eq!(SelectionMode::Add.combine(&base, [3, 4]), vec![1, 2, 3, 4]);
+        assert_eq!(SelectionMode::Toggle.combine(&base, [3, 4]), vec![1, 2, 4]);
+        assert_eq!(SelectionMode::Subtract.combine(&base, [3, 4]), vec![1, 2]);
+    }
+
+    #[test]
+    pub fn test_collapsed_nodes_undo() {
+        let mut state = GraphEditorState::<(), (), (), (), ()>::default();
+        let node = state.graph.add_node(String::new(), (), |_, _| {});
+        assert!(!state.is_node_collapsed(node));
+
+        state.record_undo_step(None);
+        state.set_node_collapsed(node, true);
+        assert!(state.is_node_collapsed(node));
 
-        self.zoom += zoom_delta;
-        self.pan += point * zoom_delta;
+        assert!(state.undo());
+        assert!(!state.is_node_collapsed(node));
+        assert!(state.redo());
+        assert!(state.is_node_collapsed(node));
     }
 }
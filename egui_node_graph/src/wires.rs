@@ -0,0 +1,186 @@
+use crate::utils::ColorUtils;
+use egui::epaint::CubicBezierShape;
+use egui::*;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// Length of the dashes of dashed wires, at zoom 1.0.
+const DASH_LENGTH: f32 = 10.0;
+/// Length of the gaps between the dashes of dashed wires, at zoom 1.0.
+const DASH_GAP: f32 = 6.0;
+/// How far orthogonal wires go straight out of a port before turning, at
+/// zoom 1.0.
+const ORTHOGONAL_OFFSET: f32 = 20.0;
+
+/// The shape of the wires drawn between ports. See
+/// [`crate::GraphEditorState::wire_style`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum WireStyle {
+    /// Smooth curves leaving and entering ports horizontally.
+    #[default]
+    Bezier,
+    /// Straight lines from port to port.
+    Straight,
+    /// Horizontal and vertical lines, turning at right angles.
+    Orthogonal,
+}
+
+/// The stroke of the wires carrying a data type. See
+/// [`crate::DataTypeTrait::wire_stroke`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WireStroke {
+    pub color: Color32,
+    /// Width of the wire, at zoom 1.0.
+    pub width: f32,
+    pub dashed: bool,
+}
+
+impl WireStroke {
+    /// A solid stroke of the default width.
+    pub fn new(color: Color32) -> Self {
+        Self {
+            color,
+            width: 5.0,
+            dashed: false,
+        }
+    }
+}
+
+/// Whether a wire is drawn with emphasis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireHighlight {
+    None,
+    /// The cursor is over the wire.
+    Hovered,
+    /// The wire is [`crate::GraphEditorState::selected_connection`].
+    Selected,
+}
+
+/// A wire to paint, passed to [`crate::DataTypeTrait::paint_wire`]. All
+/// positions are in screen space.
+#[derive(Debug, Clone)]
+pub struct Wire {
+    /// The output port, the reroute points and the input port the wire goes
+    /// through, in order.
+    pub points: Vec<Pos2>,
+    /// The line following the current [`WireStyle`] through all the points.
+    pub path: Vec<Pos2>,
+    pub stroke: WireStroke,
+    pub highlight: WireHighlight,
+    /// Color of the outline drawn around the wire when it is selected. See
+    /// [`crate::GraphStyle::wire_selection`].
+    pub selection_color: Color32,
+    pub zoom: f32,
+}
+
+impl Wire {
+    /// Paints the wire along its path. This is the default implementation of
+    /// [`crate::DataTypeTrait::paint_wire`].
+    pub fn paint(&self, painter: &Painter) {
+        let mut stroke = Stroke::new(self.stroke.width * self.zoom, self.stroke.color);
+        match self.highlight {
+            WireHighlight::None => {}
+            WireHighlight::Hovered => {
+                stroke.width *= 1.4;
+                stroke.color = stroke.color.lighten(1.3);
+            }
+            WireHighlight::Selected => {
+                painter.add(Shape::line(
+                    self.path.clone(),
+                    Stroke::new(stroke.width + 4.0 * self.zoom, self.selection_color),
+                ));
+            }
+        }
+        if self.stroke.dashed {
+            painter.extend(Shape::dashed_line(
+                &self.path,
+                stroke,
+                DASH_LENGTH * self.zoom,
+                DASH_GAP * self.zoom,
+            ));
+        } else {
+            painter.add(Shape::line(self.path.clone(), stroke));
+        }
+    }
+}
+
+/// Returns the line drawn for a wire going from `src_pos` to `dst_pos`, in
+/// screen space.
+pub fn wire_path(style: WireStyle, src_pos: Pos2, dst_pos: Pos2, zoom: f32) -> Vec<Pos2> {
+    match style {
+        WireStyle::Bezier => {
+            let control_scale = ((dst_pos.x - src_pos.x) / 2.0).max(30.0 * zoom);
+            let src_control = src_pos + Vec2::X * control_scale;
+            let dst_control = dst_pos - Vec2::X * control_scale;
+            CubicBezierShape::from_points_stroke(
+                [src_pos, src_control, dst_control, dst_pos],
+                false,
+                Color32::TRANSPARENT,
+                Stroke::NONE,
+            )
+            .flatten(Some(0.5))
+        }
+        WireStyle::Straight => vec![src_pos, dst_pos],
+        WireStyle::Orthogonal => {
+            let offset = ORTHOGONAL_OFFSET * zoom;
+            if dst_pos.x - src_pos.x >= 2.0 * offset {
+                let mid_x = (src_pos.x + dst_pos.x) / 2.0;
+                vec![
+                    src_pos,
+                    pos2(mid_x, src_pos.y),
+                    pos2(mid_x, dst_pos.y),
+                    dst_pos,
+                ]
+            } else {
+                // Wires going backwards loop around between the two ports
+                let mid_y = (src_pos.y + dst_pos.y) / 2.0;
+                vec![
+                    src_pos,
+                    pos2(src_pos.x + offset, src_pos.y),
+                    pos2(src_pos.x + offset, mid_y),
+                    pos2(dst_pos.x - offset, mid_y),
+                    pos2(dst_pos.x - offset, dst_pos.y),
+                    dst_pos,
+                ]
+            }
+        }
+    }
+}
+
+/// Returns the distance from `pos` to the line going through `path`.
+pub fn distance_to_path(pos: Pos2, path: &[Pos2]) -> f32 {
+    path.windows(2)
+        .map(|segment| {
+            let (a, b) = (segment[0], segment[1]);
+            let t = ((pos - a).dot(b - a) / (b - a).length_sq().max(f32::EPSILON)).clamp(0.0, 1.0);
+            pos.distance(a + (b - a) * t)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_wire_paths() {
+        let (src, dst) = (pos2(0.0, 0.0), pos2(100.0, 50.0));
+        assert_eq!(
+            wire_path(WireStyle::Straight, src, dst, 1.0),
+            vec![src, dst]
+        );
+        assert_eq!(
+            wire_path(WireStyle::Orthogonal, src, dst, 1.0),
+            vec![src, pos2(50.0, 0.0), pos2(50.0, 50.0), dst]
+        );
+
+        let bezier = wire_path(WireStyle::Bezier, src, dst, 1.0);
+        assert_eq!(bezier.first(), Some(&src));
+        assert_eq!(bezier.last(), Some(&dst));
+
+        let path = wire_path(WireStyle::Orthogonal, src, dst, 1.0);
+        assert_eq!(distance_to_path(pos2(60.0, 25.0), &path), 10.0);
+    }
+}
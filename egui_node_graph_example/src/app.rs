@@ -60,6 +60,27 @@ impl eframe::App for NodeGraphExample {
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 egui::widgets::global_dark_light_mode_switch(ui);
+                let mut show_minimap = self.state.minimap.is_some();
+                if ui.checkbox(&mut show_minimap, "Minimap").changed() {
+                    self.state.minimap = show_minimap.then(Minimap::default);
+                }
+                ui.checkbox(&mut self.state.grid.show, "Grid");
+                ui.checkbox(&mut self.state.grid.snap, "Snap to grid");
+                egui::ComboBox::from_id_source("wire_style")
+                    .selected_text(format!("{:?} wires", self.state.wire_style))
+                    .show_ui(ui, |ui| {
+                        for style in [
+                            WireStyle::Bezier,
+                            WireStyle::Straight,
+                            WireStyle::Orthogonal,
+                        ] {
+                            ui.selectable_value(
+                                &mut self.state.wire_style,
+                                style,
+                                format!("{style:?}"),
+                            );
+                        }
+                    });
             });
         });
 
@@ -88,7 +109,14 @@ impl eframe::App for NodeGraphExample {
         // If an active node is set, evaluate and display its result
         if let Some(node) = self.user_state.active_node {
             if self.state.graph.nodes.contains_key(node) {
-                let text = match evaluate_node(&self.state.graph, node, &mut HashMap::new()) {
+                // Evaluation is recursive, so it would never finish on a cycle
+                let result = self
+                    .state
+                    .graph
+                    .topological_order()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| evaluate_node(&self.state.graph, node, &mut HashMap::new()));
+                let text = match result {
                     Ok(value) => format!("The result is: {:?}", value),
                     Err(err) => format!("Execution error: {}", err),
                 };
@@ -16,4 +16,5 @@ pub mod multiply_scalar;
 pub mod rotate90_filter;
 pub mod subtract_scalar;
 pub mod subtract_vector;
+pub mod sum_scalars;
 pub mod vector_times_scalar;
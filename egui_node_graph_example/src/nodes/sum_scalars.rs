@@ -0,0 +1,21 @@
+use crate::types::{MyDataType, MyNodeData, MyValueType};
+use crate::utils::Evaluator;
+use egui_node_graph::*;
+
+pub fn build_node(graph: &mut Graph<MyNodeData, MyDataType, MyValueType>, node_id: NodeId) {
+    graph.add_wide_input_param(
+        node_id,
+        "values".to_string(),
+        MyDataType::Scalar,
+        MyValueType::Scalar { value: 0.0 },
+        InputParamKind::ConnectionOnly,
+        true,
+        None,
+    );
+    graph.add_output_param(node_id, "out".to_string(), MyDataType::Scalar);
+}
+
+pub fn evaluate(evaluator: &mut Evaluator<'_>) -> anyhow::Result<MyValueType> {
+    let sum = evaluator.connected_scalars("values")?.into_iter().sum();
+    evaluator.output_scalar("out", sum)
+}
@@ -10,13 +10,13 @@ use std::collections::HashMap;
 use wasm_bindgen_futures::spawn_local;
 
 /// The NodeData holds a custom data struct inside each node.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MyNodeData {
     pub template: MyNodeTemplate,
 }
 
 /// `DataType`s define the possible range of connections when attaching two ports together.
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MyDataType {
     Scalar,
     Vec2,
@@ -119,6 +119,7 @@ pub enum MyNodeTemplate {
     AddScalar,
     SubtractScalar,
     MultiplyScalar,
+    SumScalars,
     MakeVector,
     AddVector,
     SubtractVector,
@@ -156,6 +157,8 @@ pub struct NodeDefinition {
     pub evaluate: fn(&mut Evaluator<'_>) -> anyhow::Result<MyValueType>,
     pub label: &'static str,
     pub categories: &'static [&'static str],
+    pub description: &'static str,
+    pub keywords: &'static [&'static str],
 }
 
 impl NodeDefinition {
@@ -167,6 +170,8 @@ impl NodeDefinition {
                 evaluate: nodes::make_scalar::evaluate,
                 label: "New Scalar",
                 categories: &["Scalar"],
+                description: "A constant number.",
+                keywords: &["number", "constant", "value", "float"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::AddScalar,
@@ -174,6 +179,8 @@ impl NodeDefinition {
                 evaluate: nodes::add_scalar::evaluate,
                 label: "Scalar Add",
                 categories: &["Scalar"],
+                description: "Adds two numbers.",
+                keywords: &["plus", "sum", "+"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::SubtractScalar,
@@ -181,6 +188,8 @@ impl NodeDefinition {
                 evaluate: nodes::subtract_scalar::evaluate,
                 label: "Scalar Subtract",
                 categories: &["Scalar"],
+                description: "Subtracts the second number from the first.",
+                keywords: &["minus", "difference", "-"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::MultiplyScalar,
@@ -188,6 +197,17 @@ impl NodeDefinition {
                 evaluate: nodes::multiply_scalar::evaluate,
                 label: "Scalar Multiply",
                 categories: &["Scalar"],
+                description: "Multiplies two numbers.",
+                keywords: &["times", "product", "*"],
+            },
+            NodeDefinition {
+                template: MyNodeTemplate::SumScalars,
+                build: nodes::sum_scalars::build_node,
+                evaluate: nodes::sum_scalars::evaluate,
+                label: "Scalar Sum",
+                categories: &["Scalar"],
+                description: "Adds up all the numbers connected to its input.",
+                keywords: &["total", "add all", "accumulate"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::MakeVector,
@@ -195,6 +215,8 @@ impl NodeDefinition {
                 evaluate: nodes::make_vector::evaluate,
                 label: "New Vector",
                 categories: &["Vector"],
+                description: "A constant 2D vector.",
+                keywords: &["vec2", "point", "constant"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::AddVector,
@@ -202,6 +224,8 @@ impl NodeDefinition {
                 evaluate: nodes::add_vector::evaluate,
                 label: "Vector Add",
                 categories: &["Vector"],
+                description: "Adds two vectors component-wise.",
+                keywords: &["plus", "sum", "+"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::SubtractVector,
@@ -209,6 +233,8 @@ impl NodeDefinition {
                 evaluate: nodes::subtract_vector::evaluate,
                 label: "Vector Subtract",
                 categories: &["Vector"],
+                description: "Subtracts the second vector from the first, component-wise.",
+                keywords: &["minus", "difference", "-"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::VectorTimesScalar,
@@ -216,6 +242,8 @@ impl NodeDefinition {
                 evaluate: nodes::vector_times_scalar::evaluate,
                 label: "Vector Times Scalar",
                 categories: &["Vector", "Scalar"],
+                description: "Scales a vector by a number.",
+                keywords: &["multiply", "scale", "*"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::GrayscaleFilter,
@@ -223,6 +251,8 @@ impl NodeDefinition {
                 evaluate: nodes::grayscale_filter::evaluate, // Define evaluation logic for image filtering
                 label: "Grayscale Filter",
                 categories: &["Image"],
+                description: "Removes the colors of an image, keeping its brightness.",
+                keywords: &["desaturate", "black and white", "monochrome"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::MakeImage,
@@ -230,6 +260,8 @@ impl NodeDefinition {
                 evaluate: nodes::make_image::evaluate,
                 label: "Make Image",
                 categories: &["Image"],
+                description: "An image loaded from a file.",
+                keywords: &["load", "open", "file", "picture"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::BlurFilter,
@@ -237,6 +269,8 @@ impl NodeDefinition {
                 evaluate: nodes::blur_filter::evaluate,
                 label: "Blur Filter",
                 categories: &["Image"],
+                description: "Smooths an image with a gaussian blur.",
+                keywords: &["gaussian", "smooth", "soften"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::InvertFilter,
@@ -244,6 +278,8 @@ impl NodeDefinition {
                 evaluate: nodes::invert_filter::evaluate,
                 label: "Invert Filter",
                 categories: &["Image"],
+                description: "Inverts the colors of an image.",
+                keywords: &["negative", "negate"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::BrightenFilter,
@@ -251,6 +287,8 @@ impl NodeDefinition {
                 evaluate: nodes::brighten_filter::evaluate,
                 label: "Brighten Filter",
                 categories: &["Image"],
+                description: "Makes an image lighter or darker.",
+                keywords: &["brightness", "exposure", "lighten", "darken"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::ContrastFilter,
@@ -258,6 +296,8 @@ impl NodeDefinition {
                 evaluate: nodes::contrast_filter::evaluate,
                 label: "Contrast Filter",
                 categories: &["Image"],
+                description: "Increases or decreases the contrast of an image.",
+                keywords: &["levels"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::FlipHorizontalFilter,
@@ -265,6 +305,8 @@ impl NodeDefinition {
                 evaluate: nodes::flip_horizontal_filter::evaluate,
                 label: "Flip Horizontal Filter",
                 categories: &["Image"],
+                description: "Mirrors an image from left to right.",
+                keywords: &["mirror"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::FlipVerticalFilter,
@@ -272,6 +314,8 @@ impl NodeDefinition {
                 evaluate: nodes::flip_vertical_filter::evaluate,
                 label: "Flip Vertical Filter",
                 categories: &["Image"],
+                description: "Mirrors an image upside down.",
+                keywords: &["mirror"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::Rotate90Filter,
@@ -279,6 +323,8 @@ impl NodeDefinition {
                 evaluate: nodes::rotate90_filter::evaluate,
                 label: "Rotate 90 Filter",
                 categories: &["Image"],
+                description: "Rotates an image by a quarter turn.",
+                keywords: &["turn", "orientation"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::HueRotateFilter,
@@ -286,6 +332,8 @@ impl NodeDefinition {
                 evaluate: nodes::hue_rotate_filter::evaluate,
                 label: "Hue Rotate Filter",
                 categories: &["Image"],
+                description: "Shifts the hue of every pixel of an image.",
+                keywords: &["color shift", "hue shift", "tint"],
             },
             NodeDefinition {
                 template: MyNodeTemplate::MixFilter,
@@ -293,6 +341,8 @@ impl NodeDefinition {
                 evaluate: nodes::mix_filter::evaluate,
                 label: "Mix Filter",
                 categories: &["Image"],
+                description: "Blends two images together.",
+                keywords: &["blend", "lerp", "interpolate", "combine"],
             },
         ]
     }
@@ -315,6 +365,21 @@ impl DataTypeTrait<MyGraphState> for MyDataType {
             MyDataType::Image => Cow::Borrowed("Image"), // Name for the new data type
         }
     }
+
+    fn connection_compatibility(
+        &self,
+        input_type: &Self,
+        _user_state: &mut MyGraphState,
+    ) -> ConnectionCompatibility {
+        match (self, input_type) {
+            (a, b) if a == b => ConnectionCompatibility::Compatible,
+            // Scalars can drive vector inputs, see `Evaluator::input_vector`
+            (MyDataType::Scalar, MyDataType::Vec2) => {
+                ConnectionCompatibility::Conversion(Cow::Borrowed("Scalar to Vec2 (splat)"))
+            }
+            _ => ConnectionCompatibility::Incompatible,
+        }
+    }
 }
 
 // Implement NodeTemplateTrait for MyNodeTemplate
@@ -347,6 +412,33 @@ impl NodeTemplateTrait for MyNodeTemplate {
         }
     }
 
+    fn node_finder_description(&self, _user_state: &mut Self::UserState) -> Option<Cow<'_, str>> {
+        NodeDefinition::all_definitions()
+            .iter()
+            .find(|def| def.template == *self)
+            .map(|def| Cow::Borrowed(def.description))
+    }
+
+    fn node_finder_keywords(&self, _user_state: &mut Self::UserState) -> Vec<Cow<'_, str>> {
+        if let Some(def) = NodeDefinition::all_definitions()
+            .iter()
+            .find(|def| def.template == *self)
+        {
+            def.keywords
+                .iter()
+                .map(|keyword| Cow::Borrowed(*keyword))
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    fn node_finder_icon(&self, user_state: &mut Self::UserState) -> Option<Cow<'_, str>> {
+        self.node_finder_categories(user_state)
+            .contains(&"Image")
+            .then_some(Cow::Borrowed("🖼"))
+    }
+
     fn node_graph_label(&self, user_state: &mut Self::UserState) -> String {
         self.node_finder_label(user_state).into()
     }
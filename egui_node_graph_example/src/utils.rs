@@ -29,13 +29,24 @@ impl<'a> Evaluator<'a> {
     }
 
     pub fn input_vector(&mut self, name: &str) -> Result<egui::Vec2> {
-        self.evaluate_input(name)?.try_to_vec2()
+        match self.evaluate_input(name)? {
+            // Implicit conversion allowed by `MyDataType::connection_compatibility`
+            MyValueType::Scalar { value } => Ok(egui::Vec2::splat(value)),
+            value => value.try_to_vec2(),
+        }
     }
 
     pub fn input_scalar(&mut self, name: &str) -> Result<f32> {
         self.evaluate_input(name)?.try_to_scalar()
     }
 
+    pub fn connected_scalars(&mut self, name: &str) -> Result<Vec<f32>> {
+        evaluate_connected_inputs(self.graph, self.node_id, name, self.outputs_cache)?
+            .into_iter()
+            .map(MyValueType::try_to_scalar)
+            .collect()
+    }
+
     pub fn output_vector(&mut self, name: &str, value: egui::Vec2) -> Result<MyValueType> {
         self.populate_output(name, MyValueType::Vec2 { value })
     }
@@ -85,20 +96,43 @@ fn evaluate_input(
     let input_id = graph[node_id].get_input(param_name)?;
 
     if let Some(other_output_id) = graph.connection(input_id) {
-        if let Some(other_value) = outputs_cache.get(&other_output_id) {
-            Ok(other_value.clone())
-        } else {
-            evaluate_node(graph, graph[other_output_id].node, outputs_cache)?;
-            Ok(outputs_cache
-                .get(&other_output_id)
-                .expect("Cache should be populated")
-                .clone())
-        }
+        evaluate_output(graph, other_output_id, outputs_cache)
     } else {
         Ok(graph[input_id].value.clone())
     }
 }
 
+/// Evaluates every output connected to a wide input, in connection order.
+fn evaluate_connected_inputs(
+    graph: &MyGraph,
+    node_id: NodeId,
+    param_name: &str,
+    outputs_cache: &mut OutputsCache,
+) -> Result<Vec<MyValueType>> {
+    let input_id = graph[node_id].get_input(param_name)?;
+    graph
+        .connections(input_id)
+        .iter()
+        .map(|output_id| evaluate_output(graph, *output_id, outputs_cache))
+        .collect()
+}
+
+fn evaluate_output(
+    graph: &MyGraph,
+    output_id: OutputId,
+    outputs_cache: &mut OutputsCache,
+) -> Result<MyValueType> {
+    if let Some(value) = outputs_cache.get(&output_id) {
+        Ok(value.clone())
+    } else {
+        evaluate_node(graph, graph[output_id].node, outputs_cache)?;
+        Ok(outputs_cache
+            .get(&output_id)
+            .expect("Cache should be populated")
+            .clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;